@@ -1,7 +1,4 @@
-use hackmd_api_client_rs::{
-    ApiClient, ApiClientOptions, CommentPermissionType, CreateFolderOptions, CreateNoteOptions,
-    NotePermissionRole, RetryOptions, UpdateNoteOptions,
-};
+use hackmd_api_client_rs::prelude::*;
 use std::{env, error, io, time};
 
 fn read_access_token() -> Result<String, io::Error> {
@@ -22,7 +19,9 @@ async fn main() -> Result<(), Box<dyn error::Error>> {
         retry_options: Some(RetryOptions {
             max_retries: 3,
             base_delay: time::Duration::from_millis(200),
+            ..Default::default()
         }),
+        ..Default::default()
     };
 
     let client = ApiClient::with_options(&access_token, None, Some(options))?;
@@ -0,0 +1,157 @@
+use crate::error::{HackMDError, TimeoutError};
+use crate::{ApiClient, ApiError, Result};
+use std::collections::HashMap;
+use std::time;
+use tokio::runtime::Handle;
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+
+/// Updates that failed to write, as `(note_id, error)` pairs.
+pub type BatchFailures = Vec<(String, ApiError)>;
+
+enum BatchCommand {
+    Update { note_id: String, content: String },
+    Flush(oneshot::Sender<BatchFailures>),
+}
+
+pub struct BatchUpdater {
+    sender: mpsc::UnboundedSender<BatchCommand>,
+    worker: JoinHandle<BatchFailures>,
+}
+
+impl BatchUpdater {
+    pub(crate) fn new(client: ApiClient, debounce: time::Duration) -> Result<Self> {
+        let runtime = Handle::try_current().map_err(|_| {
+            ApiError::HackMD(HackMDError {
+                message: "BatchUpdater must be created inside a Tokio runtime".to_string(),
+            })
+        })?;
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let worker = runtime.spawn(run_worker(client, debounce, receiver));
+        Ok(Self { sender, worker })
+    }
+
+    fn closed_error() -> ApiError {
+        ApiError::HackMD(HackMDError {
+            message: "Batch updater worker is no longer running".to_string(),
+        })
+    }
+
+    /// Queues `content` for `note_id`. Each update restarts that note's debounce window, so
+    /// the PATCH is sent once the note has been quiet for the full `debounce` duration.
+    pub fn update_note_content(&self, note_id: &str, content: &str) -> Result<()> {
+        ApiClient::require_non_empty("note_id", note_id)?;
+
+        self.sender
+            .send(BatchCommand::Update {
+                note_id: note_id.to_string(),
+                content: content.to_string(),
+            })
+            .map_err(|_| Self::closed_error())
+    }
+
+    /// Writes every pending update now and returns each write that failed since the last
+    /// `flush()`, including failures from debounce-triggered writes in the background.
+    pub async fn flush(&self) -> Result<BatchFailures> {
+        let (reply, response) = oneshot::channel();
+        self.sender
+            .send(BatchCommand::Flush(reply))
+            .map_err(|_| Self::closed_error())?;
+        response.await.map_err(|_| Self::closed_error())
+    }
+
+    /// Stops accepting updates, flushes everything still pending, and waits up to `timeout`
+    /// for those writes to finish, including any time spent waiting on the client's
+    /// `rate_limit`. Returns every write that failed since the last `flush()`. On timeout
+    /// the remaining writes keep running in the background.
+    pub async fn shutdown(self, timeout: time::Duration) -> Result<BatchFailures> {
+        let Self { sender, worker } = self;
+        drop(sender);
+
+        match tokio::time::timeout(timeout, worker).await {
+            Ok(Ok(failures)) => Ok(failures),
+            Ok(Err(_)) => Err(Self::closed_error()),
+            Err(_) => Err(ApiError::Timeout(TimeoutError {
+                message: "Batch updater did not drain pending updates before the shutdown timeout"
+                    .to_string(),
+                timeout,
+            })),
+        }
+    }
+}
+
+async fn run_worker(
+    client: ApiClient,
+    debounce: time::Duration,
+    mut receiver: mpsc::UnboundedReceiver<BatchCommand>,
+) -> BatchFailures {
+    let mut pending: HashMap<String, (String, Instant)> = HashMap::new();
+    let mut failures = Vec::new();
+
+    loop {
+        let next_deadline = pending.values().map(|(_, deadline)| *deadline).min();
+        let command = match next_deadline {
+            None => receiver.recv().await,
+            Some(deadline) => match tokio::time::timeout_at(deadline, receiver.recv()).await {
+                Ok(command) => command,
+                Err(_) => {
+                    let now = Instant::now();
+                    let due: Vec<String> = pending
+                        .iter()
+                        .filter(|(_, (_, deadline))| *deadline <= now)
+                        .map(|(note_id, _)| note_id.clone())
+                        .collect();
+                    let updates = due
+                        .into_iter()
+                        .filter_map(|note_id| {
+                            pending
+                                .remove(&note_id)
+                                .map(|(content, _)| (note_id, content))
+                        })
+                        .collect();
+                    write_updates(&client, updates, &mut failures).await;
+                    continue;
+                }
+            },
+        };
+
+        match command {
+            Some(BatchCommand::Update { note_id, content }) => {
+                pending.insert(note_id, (content, Instant::now() + debounce));
+            }
+            Some(BatchCommand::Flush(reply)) => {
+                write_all(&client, &mut pending, &mut failures).await;
+                let _ = reply.send(std::mem::take(&mut failures));
+            }
+            None => {
+                write_all(&client, &mut pending, &mut failures).await;
+                return failures;
+            }
+        }
+    }
+}
+
+async fn write_all(
+    client: &ApiClient,
+    pending: &mut HashMap<String, (String, Instant)>,
+    failures: &mut BatchFailures,
+) {
+    let updates = pending
+        .drain()
+        .map(|(note_id, (content, _))| (note_id, content))
+        .collect();
+    write_updates(client, updates, failures).await;
+}
+
+async fn write_updates(
+    client: &ApiClient,
+    updates: Vec<(String, String)>,
+    failures: &mut BatchFailures,
+) {
+    for (note_id, content) in updates {
+        if let Err(err) = client.update_note_content(&note_id, &content).await {
+            failures.push((note_id, err));
+        }
+    }
+}
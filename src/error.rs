@@ -1,5 +1,5 @@
-use reqwest::header;
-use std::{error, fmt, result};
+use reqwest::{header, StatusCode};
+use std::{error, fmt, io, result, time};
 
 #[derive(Debug)]
 pub struct HackMDError {
@@ -42,6 +42,33 @@ impl fmt::Display for MissingRequiredArgument {
 
 impl error::Error for MissingRequiredArgument {}
 
+#[derive(Debug)]
+pub struct ValidationError {
+    pub message: String,
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl error::Error for ValidationError {}
+
+#[derive(Debug)]
+pub struct TimeoutError {
+    pub message: String,
+    pub timeout: time::Duration,
+}
+
+impl fmt::Display for TimeoutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({:?})", self.message, self.timeout)
+    }
+}
+
+impl error::Error for TimeoutError {}
+
 #[derive(Debug)]
 pub struct InternalServerError {
     pub message: String,
@@ -57,6 +84,44 @@ impl fmt::Display for InternalServerError {
 
 impl error::Error for InternalServerError {}
 
+#[derive(Debug)]
+pub struct ForbiddenError {
+    pub message: String,
+    pub code: u16,
+    pub status_text: String,
+}
+
+impl fmt::Display for ForbiddenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.message, self.code)
+    }
+}
+
+impl error::Error for ForbiddenError {}
+
+#[derive(Debug)]
+pub struct ConflictError {
+    pub message: String,
+    pub code: u16,
+    pub status_text: String,
+    pub suggested_permalink: Option<String>,
+}
+
+impl fmt::Display for ConflictError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.suggested_permalink {
+            Some(permalink) => write!(
+                f,
+                "{} ({}); try permalink {}",
+                self.message, self.code, permalink
+            ),
+            None => write!(f, "{} ({})", self.message, self.code),
+        }
+    }
+}
+
+impl error::Error for ConflictError {}
+
 #[derive(Debug)]
 pub struct TooManyRequestsError {
     pub message: String,
@@ -67,6 +132,18 @@ pub struct TooManyRequestsError {
     pub reset_after: Option<u64>,
 }
 
+impl TooManyRequestsError {
+    pub fn retry_after(&self) -> Option<time::Duration> {
+        let reset_at =
+            time::UNIX_EPOCH.checked_add(time::Duration::from_secs(self.reset_after?))?;
+        Some(
+            reset_at
+                .duration_since(time::SystemTime::now())
+                .unwrap_or(time::Duration::ZERO),
+        )
+    }
+}
+
 impl fmt::Display for TooManyRequestsError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
@@ -84,7 +161,11 @@ pub enum ApiError {
     HackMD(HackMDError),
     HttpResponse(HttpResponseError),
     MissingRequiredArgument(MissingRequiredArgument),
+    Validation(ValidationError),
+    Timeout(TimeoutError),
     InternalServer(InternalServerError),
+    Forbidden(ForbiddenError),
+    Conflict(ConflictError),
     TooManyRequests(TooManyRequestsError),
     Reqwest(reqwest::Error),
     Url(url::ParseError),
@@ -92,6 +173,110 @@ pub enum ApiError {
     Serde(serde_json::Error),
 }
 
+/// Maps a non-success HackMD response to the matching `ApiError` variant, the same way
+/// `ApiClient` does. Useful for raw requests made through `ApiClient::http_client()`.
+pub fn map_status_error(status: StatusCode, headers: &header::HeaderMap, body: &str) -> ApiError {
+    let status_text = status.canonical_reason().unwrap_or("Unknown").to_string();
+    let user_limit = headers
+        .get("x-ratelimit-userlimit")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(0);
+    let user_remaining = headers
+        .get("x-ratelimit-userremaining")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(0);
+    let reset_after = headers
+        .get("x-ratelimit-userreset")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse().ok());
+    let error_detail = if body.trim().is_empty() {
+        String::new()
+    } else {
+        format!(": {}", body.trim())
+    };
+
+    match status {
+        StatusCode::TOO_MANY_REQUESTS => ApiError::TooManyRequests(TooManyRequestsError {
+            message: format!(
+                "Too many requests ({} {}){}",
+                status.as_u16(),
+                status_text,
+                error_detail
+            ),
+            code: status.as_u16(),
+            status_text,
+            user_limit,
+            user_remaining,
+            reset_after,
+        }),
+        StatusCode::FORBIDDEN => ApiError::Forbidden(ForbiddenError {
+            message: format!(
+                "Forbidden ({} {}) by HackMD{}",
+                status.as_u16(),
+                status_text,
+                error_detail
+            ),
+            code: status.as_u16(),
+            status_text,
+        }),
+        StatusCode::CONFLICT => ApiError::Conflict(ConflictError {
+            message: format!(
+                "Conflict ({} {}) from HackMD{}",
+                status.as_u16(),
+                status_text,
+                error_detail
+            ),
+            code: status.as_u16(),
+            status_text,
+            suggested_permalink: None,
+        }),
+        _ if status.is_server_error() => ApiError::InternalServer(InternalServerError {
+            message: format!(
+                "HackMD internal error ({} {}){}",
+                status.as_u16(),
+                status_text,
+                error_detail
+            ),
+            code: status.as_u16(),
+            status_text,
+        }),
+        _ => ApiError::HttpResponse(HttpResponseError {
+            message: format!(
+                "Received an error response ({} {}) from HackMD{}",
+                status.as_u16(),
+                status_text,
+                error_detail
+            ),
+            code: status.as_u16(),
+            status_text,
+        }),
+    }
+}
+
+fn reqwest_error_kind(error: &reqwest::Error) -> Option<&'static str> {
+    if error.is_timeout() {
+        Some("timeout")
+    } else if error.is_connect() {
+        Some("connect")
+    } else if error.is_body() {
+        Some("body")
+    } else if error.is_decode() {
+        Some("decode")
+    } else if error.is_redirect() {
+        Some("redirect")
+    } else if error.is_status() {
+        Some("status")
+    } else if error.is_builder() {
+        Some("builder")
+    } else if error.is_request() {
+        Some("request")
+    } else {
+        None
+    }
+}
+
 impl fmt::Display for ApiError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -100,9 +285,16 @@ impl fmt::Display for ApiError {
             ApiError::MissingRequiredArgument(err) => {
                 write!(f, "Missing required argument: {}", err)
             }
+            ApiError::Validation(err) => write!(f, "Validation error: {}", err),
+            ApiError::Timeout(err) => write!(f, "Timeout: {}", err),
             ApiError::InternalServer(err) => write!(f, "Internal server error: {}", err),
+            ApiError::Forbidden(err) => write!(f, "Forbidden: {}", err),
+            ApiError::Conflict(err) => write!(f, "Conflict: {}", err),
             ApiError::TooManyRequests(err) => write!(f, "Too many requests: {}", err),
-            ApiError::Reqwest(err) => write!(f, "Request error: {}", err),
+            ApiError::Reqwest(err) => match reqwest_error_kind(err) {
+                Some(kind) => write!(f, "Request error ({}): {}", kind, err),
+                None => write!(f, "Request error: {}", err),
+            },
             ApiError::Url(err) => write!(f, "URL parse error: {}", err),
             ApiError::Header(err) => write!(f, "Header error: {}", err),
             ApiError::Serde(err) => write!(f, "Serialization error: {}", err),
@@ -136,4 +328,25 @@ impl From<serde_json::Error> for ApiError {
     }
 }
 
+impl From<ApiError> for io::Error {
+    fn from(error: ApiError) -> Self {
+        let kind = match &error {
+            ApiError::Reqwest(err) if err.is_timeout() => io::ErrorKind::TimedOut,
+            ApiError::Reqwest(err) if err.is_connect() => io::ErrorKind::ConnectionRefused,
+            ApiError::Timeout(_) => io::ErrorKind::TimedOut,
+            ApiError::Forbidden(_) => io::ErrorKind::PermissionDenied,
+            ApiError::Conflict(_) => io::ErrorKind::AlreadyExists,
+            ApiError::HttpResponse(err) if err.code == 401 => io::ErrorKind::PermissionDenied,
+            ApiError::HttpResponse(err) if err.code == 404 => io::ErrorKind::NotFound,
+            ApiError::MissingRequiredArgument(_) | ApiError::Validation(_) => {
+                io::ErrorKind::InvalidInput
+            }
+            ApiError::Serde(_) => io::ErrorKind::InvalidData,
+            _ => io::ErrorKind::Other,
+        };
+
+        io::Error::new(kind, error)
+    }
+}
+
 pub type Result<T> = result::Result<T, ApiError>;
@@ -0,0 +1,59 @@
+use hdrhistogram::Histogram;
+use std::collections::HashMap;
+use std::sync::{Mutex, PoisonError};
+use std::time;
+
+// One hour; slower responses are clamped to this bucket.
+const MAX_TRACKED_MICROS: u64 = 3_600_000_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LatencySnapshot {
+    pub count: u64,
+    pub p50: time::Duration,
+    pub p95: time::Duration,
+    pub p99: time::Duration,
+    pub max: time::Duration,
+}
+
+#[derive(Default)]
+pub(crate) struct LatencyRecorder {
+    histograms: Mutex<HashMap<String, Histogram<u64>>>,
+}
+
+impl LatencyRecorder {
+    pub(crate) fn record(&self, endpoint: String, elapsed: time::Duration) {
+        let micros = u64::try_from(elapsed.as_micros())
+            .unwrap_or(u64::MAX)
+            .max(1);
+        let mut histograms = self
+            .histograms
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        histograms
+            .entry(endpoint)
+            .or_insert_with(|| {
+                Histogram::new_with_bounds(1, MAX_TRACKED_MICROS, 3)
+                    .expect("histogram bounds are valid")
+            })
+            .saturating_record(micros);
+    }
+
+    pub(crate) fn snapshot(&self) -> HashMap<String, LatencySnapshot> {
+        let micros = time::Duration::from_micros;
+        self.histograms
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .map(|(endpoint, histogram)| {
+                let snapshot = LatencySnapshot {
+                    count: histogram.len(),
+                    p50: micros(histogram.value_at_quantile(0.50)),
+                    p95: micros(histogram.value_at_quantile(0.95)),
+                    p99: micros(histogram.value_at_quantile(0.99)),
+                    max: micros(histogram.max()),
+                };
+                (endpoint.clone(), snapshot)
+            })
+            .collect()
+    }
+}
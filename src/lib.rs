@@ -1,23 +1,143 @@
+pub mod batch;
 pub mod error;
+#[cfg(feature = "latency")]
+mod latency;
+pub mod prelude;
+mod rate_limit;
+mod single_flight;
+pub mod team;
+#[cfg(any(test, feature = "testkit"))]
+pub mod testkit;
 pub mod types;
 
-pub use error::{ApiError, Result};
+pub use batch::{BatchFailures, BatchUpdater};
+pub use error::{map_status_error, ApiError, Result};
+#[cfg(feature = "latency")]
+pub use latency::LatencySnapshot;
+pub use team::TeamClient;
 pub use types::*;
 
-use crate::error::{
-    HttpResponseError, InternalServerError, MissingRequiredArgument, TooManyRequestsError,
-};
-use reqwest::{header, Client as HttpClient, Response, StatusCode, Url};
+use crate::error::{HackMDError, MissingRequiredArgument, TimeoutError, ValidationError};
+#[cfg(feature = "latency")]
+use crate::latency::LatencyRecorder;
+use crate::rate_limit::RateLimiter;
+use crate::single_flight::{BufferedResponse, Flight, SingleFlight};
+use reqwest::{header, Client as HttpClient, Method, RequestBuilder, Response, StatusCode, Url};
 use serde_json::Value;
-use std::{future, time};
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    future,
+    sync::atomic::{AtomicU64, Ordering},
+    sync::{Arc, Mutex, MutexGuard},
+    time,
+};
 
 const DEFAULT_BASE_URL: &str = "https://api.hackmd.io/v1/";
+// Fan-out limit for helpers that fetch many teams or notes at once.
+const MAX_CONCURRENT_REQUESTS: usize = 4;
+const DEFAULT_BACKOFF_MULTIPLIER: f64 = 2.0;
+// Longest a retry will sleep for a rate-limit reset before giving up on the 429 instead.
+const MAX_RATE_LIMIT_WAIT: time::Duration = time::Duration::from_secs(60);
+// Upper bound on one exponential backoff sleep, whatever the attempt count or multiplier.
+const MAX_BACKOFF_DELAY: time::Duration = time::Duration::from_secs(60);
+
+fn next_permalink(permalink: &str) -> String {
+    match permalink.rsplit_once('-') {
+        Some((base, suffix)) if !base.is_empty() => match suffix.parse::<u32>() {
+            Ok(number) => format!("{base}-{}", number.saturating_add(1)),
+            Err(_) => format!("{permalink}-2"),
+        },
+        _ => format!("{permalink}-2"),
+    }
+}
+
+fn generate_idempotency_key() -> String {
+    use std::hash::{BuildHasher, Hasher};
+
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let mut hasher = std::collections::hash_map::RandomState::new().build_hasher();
+    hasher.write_u64(COUNTER.fetch_add(1, Ordering::Relaxed));
+    let nanos = time::SystemTime::now()
+        .duration_since(time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    format!("{:016x}-{:x}", hasher.finish(), nanos)
+}
+
+/// Keys accepted by `ApiClientOptions::endpoint_retry_overrides`.
+const RETRY_ENDPOINTS: &[&str] = &[
+    "create_folder",
+    "create_note",
+    "create_team_folder",
+    "create_team_note",
+    "delete_folder",
+    "delete_note",
+    "delete_team_folder",
+    "delete_team_note",
+    "get_folder",
+    "get_folder_order",
+    "get_folders",
+    "get_history",
+    "get_me",
+    "get_note",
+    "get_note_list",
+    "get_published_html",
+    "get_team_folder",
+    "get_team_folder_order",
+    "get_team_folders",
+    "get_team_note",
+    "get_team_notes",
+    "get_teams",
+    "update_folder",
+    "update_folder_order",
+    "update_note",
+    "update_team_folder",
+    "update_team_folder_order",
+    "update_team_note",
+    "upload_note_image",
+];
 
 #[derive(Clone)]
 pub struct ApiClientOptions {
     pub wrap_response_errors: bool,
     pub timeout: Option<time::Duration>,
+    pub connect_timeout: Option<time::Duration>,
     pub retry_options: Option<RetryOptions>,
+    /// Replaces `retry_options` for individual operations, keyed by `ApiClient` method name
+    /// such as `"get_note"` or `"delete_note"`; `None` disables retries for that operation.
+    /// Variants share their base method's key, so `get_me_with_stats` uses `"get_me"` and
+    /// `create_note_content_with_type` uses `"create_note"`.
+    pub endpoint_retry_overrides: HashMap<&'static str, Option<RetryOptions>>,
+    pub max_response_bytes: Option<usize>,
+    pub normalize_line_endings: bool,
+    /// Deserializes successful responses from a top-level `data` field when present, falling
+    /// back to the whole body when `data` is missing or does not match the expected type.
+    pub unwrap_data_envelope: bool,
+    /// Fills a missing `title` on `create_note`/`create_team_note` from the content's leading
+    /// `# Heading`. The content itself is sent unchanged.
+    pub auto_title: bool,
+    /// Rejects note content containing null bytes or other control characters (except tab,
+    /// line feed and carriage return) with `ApiError::Validation` before it is uploaded.
+    pub reject_binary_content: bool,
+    /// Disables TLS certificate validation. Only enable this for trusted self-hosted
+    /// deployments, as it exposes the access token to man-in-the-middle attacks.
+    pub accept_invalid_certs: bool,
+    pub single_flight: bool,
+    pub http2_prior_knowledge: bool,
+    pub overall_timeout: Option<time::Duration>,
+    pub rate_limit: Option<RateLimit>,
+    pub verify_on_build: bool,
+    pub local_address: Option<std::net::IpAddr>,
+    pub team_visibility_guard: Option<TeamVisibilityGuard>,
+    /// Secondary API base URL. Idempotent requests whose retries against the primary all fail
+    /// with a retryable error are sent here once before the error is returned. `POST`
+    /// requests only fail over when `RetryOptions::retry_non_idempotent` is set.
+    pub fallback_base_url: Option<Url>,
+    /// Keeps cookies set by the server, such as an SSO gateway's session cookie, and sends
+    /// them on later requests and redirects. The jar is reset when the access token changes.
+    #[cfg(feature = "cookies")]
+    pub cookie_store: bool,
 }
 
 impl Default for ApiClientOptions {
@@ -25,15 +145,132 @@ impl Default for ApiClientOptions {
         Self {
             wrap_response_errors: true,
             timeout: Some(time::Duration::from_secs(30)),
+            connect_timeout: None,
             retry_options: Some(RetryOptions::default()),
+            endpoint_retry_overrides: HashMap::new(),
+            max_response_bytes: None,
+            normalize_line_endings: false,
+            unwrap_data_envelope: false,
+            auto_title: false,
+            reject_binary_content: false,
+            accept_invalid_certs: false,
+            single_flight: false,
+            http2_prior_knowledge: false,
+            overall_timeout: None,
+            rate_limit: None,
+            verify_on_build: false,
+            local_address: None,
+            team_visibility_guard: None,
+            fallback_base_url: None,
+            #[cfg(feature = "cookies")]
+            cookie_store: false,
+        }
+    }
+}
+
+impl ApiClientOptions {
+    pub fn validate(&self) -> Result<()> {
+        if self.timeout.is_some_and(|timeout| timeout.is_zero()) {
+            return Err(ApiClient::validation_error(
+                "timeout must be greater than zero",
+            ));
+        }
+
+        if self
+            .overall_timeout
+            .is_some_and(|timeout| timeout.is_zero())
+        {
+            return Err(ApiClient::validation_error(
+                "overall_timeout must be greater than zero",
+            ));
+        }
+
+        if let (Some(connect_timeout), Some(timeout)) = (self.connect_timeout, self.timeout) {
+            if connect_timeout > timeout {
+                return Err(ApiClient::validation_error(format!(
+                    "connect_timeout ({connect_timeout:?}) must not exceed timeout ({timeout:?})"
+                )));
+            }
+        }
+
+        if let Some(rate_limit) = &self.rate_limit {
+            if rate_limit.max_requests == 0 || rate_limit.per.is_zero() {
+                return Err(ApiClient::validation_error(
+                    "rate_limit must allow at least one request per non-zero period",
+                ));
+            }
+        }
+
+        if let Some(fallback_base_url) = &self.fallback_base_url {
+            if !matches!(fallback_base_url.scheme(), "http" | "https") {
+                return Err(ApiClient::validation_error(format!(
+                    "fallback_base_url must use http or https, got {}",
+                    fallback_base_url.scheme()
+                )));
+            }
+        }
+
+        if let Some(retry_options) = &self.retry_options {
+            self.validate_retry_options("retry_options", retry_options)?;
+        }
+
+        for (endpoint, retry_options) in &self.endpoint_retry_overrides {
+            if !RETRY_ENDPOINTS.contains(endpoint) {
+                return Err(ApiClient::validation_error(format!(
+                    "endpoint_retry_overrides has unknown endpoint {endpoint:?}"
+                )));
+            }
+            if let Some(retry_options) = retry_options {
+                let name = format!("endpoint_retry_overrides[{endpoint:?}]");
+                self.validate_retry_options(&name, retry_options)?;
+            }
         }
+
+        Ok(())
+    }
+
+    fn validate_retry_options(&self, name: &str, retry_options: &RetryOptions) -> Result<()> {
+        if retry_options.max_retries == 0 {
+            return Err(ApiClient::validation_error(format!(
+                "{name}.max_retries must be at least 1; use {name}: None to disable retries"
+            )));
+        }
+
+        if !retry_options.multiplier.is_finite() || retry_options.multiplier <= 0.0 {
+            return Err(ApiClient::validation_error(format!(
+                "{name}.multiplier must be a positive finite number, got {}",
+                retry_options.multiplier
+            )));
+        }
+
+        if let Some(timeout) = self.timeout {
+            if timeout < retry_options.base_delay {
+                return Err(ApiClient::validation_error(format!(
+                    "timeout ({timeout:?}) must not be shorter than {name}.base_delay ({:?})",
+                    retry_options.base_delay
+                )));
+            }
+        }
+
+        Ok(())
     }
 }
 
+pub type RetryPredicate = Arc<dyn Fn(&ApiError) -> bool + Send + Sync>;
+
+/// Called with `(done, total)` after each item of a bulk operation completes.
+pub type ProgressCallback = Arc<dyn Fn(usize, usize) + Send + Sync>;
+
 #[derive(Clone)]
 pub struct RetryOptions {
     pub max_retries: u32,
     pub base_delay: time::Duration,
+    pub retry_predicate: Option<RetryPredicate>,
+    pub retry_decode_errors: bool,
+    pub multiplier: f64,
+    /// Also retry POST requests such as `create_note`. Off by default because a retried
+    /// create whose first response was lost can produce a duplicate note.
+    pub retry_non_idempotent: bool,
 }
 
 impl Default for RetryOptions {
@@ -41,14 +278,150 @@ impl Default for RetryOptions {
         Self {
             max_retries: 3,
             base_delay: time::Duration::from_millis(100),
+            retry_predicate: None,
+            retry_decode_errors: false,
+            multiplier: DEFAULT_BACKOFF_MULTIPLIER,
+            retry_non_idempotent: false,
+        }
+    }
+}
+
+/// Token bucket shared by a client and all of its clones: up to `max_requests` may be sent
+/// back to back, after which requests are paced to `max_requests` per `per`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    pub max_requests: u32,
+    pub per: time::Duration,
+}
+
+/// What `create_team_note` does when a note's `read_permission` would expose it beyond a
+/// private team. Team visibility is looked up through `get_me` once and then cached.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TeamVisibilityGuard {
+    /// Creates the note anyway, logging a warning when the `tracing` feature is enabled.
+    /// Without that feature no visibility lookup is made.
+    Warn,
+    /// Fails with `ApiError::Validation` without sending the note.
+    Reject,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RequestStats {
+    pub attempts: u32,
+    pub total_wait: time::Duration,
+}
+
+/// Cache-validation and rate-limit headers returned alongside a response. `etag` and
+/// `last_modified` hold the raw header values, ready for `If-None-Match` and
+/// `If-Modified-Since` on a conditional refetch.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ResponseMeta {
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    pub rate_limit: Option<RateLimitStatus>,
+}
+
+/// HackMD's per-user quota as reported by the `x-ratelimit-user*` headers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimitStatus {
+    pub limit: u32,
+    pub remaining: u32,
+    /// Unix timestamp, in seconds, at which the quota resets.
+    pub reset_at: Option<u64>,
+}
+
+impl ResponseMeta {
+    fn from_headers(headers: &header::HeaderMap) -> Self {
+        let text = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string)
+        };
+        let rate_limit = text("x-ratelimit-userlimit")
+            .and_then(|limit| limit.parse().ok())
+            .map(|limit| RateLimitStatus {
+                limit,
+                remaining: text("x-ratelimit-userremaining")
+                    .and_then(|remaining| remaining.parse().ok())
+                    .unwrap_or(0),
+                reset_at: text("x-ratelimit-userreset").and_then(|reset| reset.parse().ok()),
+            });
+
+        Self {
+            etag: text(header::ETAG.as_str()),
+            last_modified: text(header::LAST_MODIFIED.as_str()),
+            rate_limit,
+        }
+    }
+}
+
+/// Cumulative counters shared by a client and all of its clones.
+#[derive(Debug, Default)]
+pub struct RetryMetrics {
+    total_requests: AtomicU64,
+    total_retries: AtomicU64,
+    total_429s: AtomicU64,
+    total_5xx: AtomicU64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RetryMetricsSnapshot {
+    pub total_requests: u64,
+    pub total_retries: u64,
+    pub total_429s: u64,
+    pub total_5xx: u64,
+}
+
+impl RetryMetrics {
+    pub fn snapshot(&self) -> RetryMetricsSnapshot {
+        RetryMetricsSnapshot {
+            total_requests: self.total_requests.load(Ordering::Relaxed),
+            total_retries: self.total_retries.load(Ordering::Relaxed),
+            total_429s: self.total_429s.load(Ordering::Relaxed),
+            total_5xx: self.total_5xx.load(Ordering::Relaxed),
+        }
+    }
+
+    fn record_status(&self, status: StatusCode) {
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            self.total_429s.fetch_add(1, Ordering::Relaxed);
+        } else if status.is_server_error() {
+            self.total_5xx.fetch_add(1, Ordering::Relaxed);
         }
     }
 }
 
+/// The last `get_note_list` response and its `ETag`, replayed when the server answers a
+/// conditional request with `304 Not Modified`.
+struct NoteListCache {
+    etag: String,
+    notes: Vec<Note>,
+}
+
+#[derive(Clone)]
 pub struct ApiClient {
     http_client: HttpClient,
+    public_http_client: HttpClient,
     base_url: Url,
+    fallback_base_url: Option<Url>,
+    default_headers: header::HeaderMap,
     options: ApiClientOptions,
+    single_flight: Arc<SingleFlight>,
+    rate_limiter: Option<Arc<RateLimiter>>,
+    metrics: Arc<RetryMetrics>,
+    team_visibility: Arc<Mutex<HashMap<String, TeamVisibilityType>>>,
+    note_list_cache: Arc<Mutex<Option<NoteListCache>>>,
+    #[cfg(feature = "latency")]
+    latency: Arc<LatencyRecorder>,
+    #[cfg(feature = "request-id")]
+    last_request_id: Arc<Mutex<Option<String>>>,
+}
+
+impl AsRef<ApiClientOptions> for ApiClient {
+    fn as_ref(&self) -> &ApiClientOptions {
+        &self.options
+    }
 }
 
 impl ApiClient {
@@ -58,7 +431,7 @@ impl ApiClient {
         })
     }
 
-    fn require_non_empty(value_name: &str, value: &str) -> Result<()> {
+    pub(crate) fn require_non_empty(value_name: &str, value: &str) -> Result<()> {
         if value.trim().is_empty() {
             return Err(Self::missing_required_argument(format!(
                 "Missing {value_name} when calling HackMD API"
@@ -76,8 +449,16 @@ impl ApiClient {
         }
     }
 
-    fn resource_url(&self, segments: &[&str]) -> Result<Url> {
-        let mut url = self.base_url.clone();
+    fn normalized_fallback_base_url(options: &ApiClientOptions) -> Result<Option<Url>> {
+        options
+            .fallback_base_url
+            .as_ref()
+            .map(|url| Ok(Url::parse(&Self::normalized_base_url(url.as_str()))?))
+            .transpose()
+    }
+
+    fn resource_url(base_url: &Url, segments: &[&str]) -> Result<Url> {
+        let mut url = base_url.clone();
         url.path_segments_mut()
             .map_err(|_| url::ParseError::RelativeUrlWithoutBase)?
             .pop_if_empty()
@@ -85,54 +466,194 @@ impl ApiClient {
         Ok(url)
     }
 
-    fn note_url(&self, note_id: &str) -> Result<Url> {
+    fn note_url(base_url: &Url, note_id: &str) -> Result<Url> {
         Self::require_non_empty("note_id", note_id)?;
-        self.resource_url(&["notes", note_id])
+        Self::resource_url(base_url, &["notes", note_id])
     }
 
-    fn note_image_url(&self, note_id: &str) -> Result<Url> {
+    fn note_image_url(base_url: &Url, note_id: &str) -> Result<Url> {
         Self::require_non_empty("note_id", note_id)?;
-        self.resource_url(&["notes", note_id, "images"])
+        Self::resource_url(base_url, &["notes", note_id, "images"])
     }
 
-    fn folders_url(&self) -> Result<Url> {
-        self.resource_url(&["folders"])
+    fn folders_url(base_url: &Url) -> Result<Url> {
+        Self::resource_url(base_url, &["folders"])
     }
 
-    fn folder_order_url(&self) -> Result<Url> {
-        self.resource_url(&["folders", "folder-order"])
+    fn folder_order_url(base_url: &Url) -> Result<Url> {
+        Self::resource_url(base_url, &["folders", "folder-order"])
     }
 
-    fn folder_url(&self, folder_id: &str) -> Result<Url> {
+    fn folder_url(base_url: &Url, folder_id: &str) -> Result<Url> {
         Self::require_non_empty("folder_id", folder_id)?;
-        self.resource_url(&["folders", folder_id])
+        Self::resource_url(base_url, &["folders", folder_id])
     }
 
-    fn team_notes_url(&self, team_path: &str) -> Result<Url> {
+    fn team_notes_url(base_url: &Url, team_path: &str) -> Result<Url> {
         Self::require_non_empty("team_path", team_path)?;
-        self.resource_url(&["teams", team_path, "notes"])
+        Self::resource_url(base_url, &["teams", team_path, "notes"])
     }
 
-    fn team_note_url(&self, team_path: &str, note_id: &str) -> Result<Url> {
+    fn team_note_url(base_url: &Url, team_path: &str, note_id: &str) -> Result<Url> {
         Self::require_non_empty("team_path", team_path)?;
         Self::require_non_empty("note_id", note_id)?;
-        self.resource_url(&["teams", team_path, "notes", note_id])
+        Self::resource_url(base_url, &["teams", team_path, "notes", note_id])
     }
 
-    fn team_folders_url(&self, team_path: &str) -> Result<Url> {
+    fn team_folders_url(base_url: &Url, team_path: &str) -> Result<Url> {
         Self::require_non_empty("team_path", team_path)?;
-        self.resource_url(&["teams", team_path, "folders"])
+        Self::resource_url(base_url, &["teams", team_path, "folders"])
     }
 
-    fn team_folder_order_url(&self, team_path: &str) -> Result<Url> {
+    fn team_folder_order_url(base_url: &Url, team_path: &str) -> Result<Url> {
         Self::require_non_empty("team_path", team_path)?;
-        self.resource_url(&["teams", team_path, "folders", "folder-order"])
+        Self::resource_url(base_url, &["teams", team_path, "folders", "folder-order"])
     }
 
-    fn team_folder_url(&self, team_path: &str, folder_id: &str) -> Result<Url> {
+    fn team_folder_url(base_url: &Url, team_path: &str, folder_id: &str) -> Result<Url> {
         Self::require_non_empty("team_path", team_path)?;
         Self::require_non_empty("folder_id", folder_id)?;
-        self.resource_url(&["teams", team_path, "folders", folder_id])
+        Self::resource_url(base_url, &["teams", team_path, "folders", folder_id])
+    }
+
+    fn normalized_content<'a>(&self, content: &'a str) -> Cow<'a, str> {
+        if self.options.normalize_line_endings && content.contains("\r\n") {
+            Cow::Owned(content.replace("\r\n", "\n"))
+        } else {
+            Cow::Borrowed(content)
+        }
+    }
+
+    fn check_content(&self, content: Option<&str>) -> Result<()> {
+        if !self.options.reject_binary_content {
+            return Ok(());
+        }
+
+        let suspicious = content.and_then(|content| {
+            content
+                .char_indices()
+                .find(|(_, c)| c.is_control() && !matches!(c, '\t' | '\n' | '\r'))
+        });
+        match suspicious {
+            Some((offset, c)) => Err(Self::validation_error(format!(
+                "content contains control character {c:?} at byte {offset}; is it binary data?"
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    fn normalized_create_payload<'a>(
+        &self,
+        payload: &'a CreateNoteOptions,
+    ) -> Cow<'a, CreateNoteOptions> {
+        let mut payload = match payload
+            .content
+            .as_deref()
+            .map(|c| self.normalized_content(c))
+        {
+            Some(Cow::Owned(content)) => Cow::Owned(CreateNoteOptions {
+                content: Some(content),
+                ..payload.clone()
+            }),
+            _ => Cow::Borrowed(payload),
+        };
+
+        if self.options.auto_title && payload.title.is_none() {
+            if let Some(title) = payload.content.as_deref().and_then(leading_h1) {
+                payload.to_mut().title = Some(title);
+            }
+        }
+        payload
+    }
+
+    fn normalized_update_payload<'a>(
+        &self,
+        payload: &'a UpdateNoteOptions,
+    ) -> Cow<'a, UpdateNoteOptions> {
+        match payload
+            .content
+            .as_deref()
+            .map(|c| self.normalized_content(c))
+        {
+            Some(Cow::Owned(content)) => Cow::Owned(UpdateNoteOptions {
+                content: Some(content),
+                ..payload.clone()
+            }),
+            _ => Cow::Borrowed(payload),
+        }
+    }
+
+    fn with_content_body(
+        &self,
+        request: RequestBuilder,
+        content: &str,
+        content_type: NoteContentType,
+    ) -> RequestBuilder {
+        let content = self.normalized_content(content);
+        match content_type {
+            NoteContentType::Json => request.json(&content),
+            _ => request
+                .header(header::CONTENT_TYPE, content_type.mime_type())
+                .body(content.into_owned()),
+        }
+    }
+
+    fn with_suggested_permalink(error: ApiError, payload: &CreateNoteOptions) -> ApiError {
+        match error {
+            ApiError::Conflict(mut conflict) => {
+                conflict.suggested_permalink = payload.permalink.as_deref().map(next_permalink);
+                ApiError::Conflict(conflict)
+            }
+            error => error,
+        }
+    }
+
+    #[cfg(feature = "tracing")]
+    fn log_payload_size(operation: &str, body: &bytes::Bytes) {
+        tracing::debug!(
+            operation,
+            payload_bytes = body.len(),
+            "sending HackMD request"
+        );
+    }
+
+    /// Serializes a JSON payload once so every retry attempt replays the same buffered body.
+    fn json_body<T: serde::Serialize>(payload: &T) -> Result<bytes::Bytes> {
+        Ok(serde_json::to_vec(payload)?.into())
+    }
+
+    fn with_json_body(request: RequestBuilder, body: &bytes::Bytes) -> RequestBuilder {
+        request
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(body.clone())
+    }
+
+    fn idempotency_key(&self, endpoint: &str, payload: &CreateNoteOptions) -> Option<String> {
+        if payload.idempotency_key.is_some() {
+            return payload.idempotency_key.clone();
+        }
+
+        self.generated_idempotency_key(endpoint)
+    }
+
+    /// A fresh key for one create call when `endpoint` retries POSTs, so every retry of that
+    /// call carries the same `Idempotency-Key`.
+    fn generated_idempotency_key(&self, endpoint: &str) -> Option<String> {
+        let retries_posts = self
+            .retry_options_for(endpoint)
+            .is_some_and(|retry_options| retry_options.retry_non_idempotent);
+        retries_posts.then(generate_idempotency_key)
+    }
+
+    fn idempotency_headers(idempotency_key: Option<&str>) -> Result<header::HeaderMap> {
+        let mut headers = header::HeaderMap::new();
+        if let Some(idempotency_key) = idempotency_key {
+            headers.insert(
+                "idempotency-key",
+                header::HeaderValue::from_str(idempotency_key)?,
+            );
+        }
+        Ok(headers)
     }
 
     fn is_success_status(status: StatusCode) -> bool {
@@ -159,818 +680,3425 @@ impl ApiClient {
         }
 
         let options = options.unwrap_or_default();
+        options.validate()?;
+
+        let headers = Self::auth_headers(access_token)?;
+        let http_client = Self::build_http_client(headers.clone(), &options)?;
+        let base_url = Url::parse(&Self::normalized_base_url(
+            base_url.unwrap_or(DEFAULT_BASE_URL),
+        ))?;
+        if !matches!(base_url.scheme(), "http" | "https") {
+            return Err(Self::validation_error(format!(
+                "base_url must use http or https, got {}",
+                base_url.scheme()
+            )));
+        }
+
+        Ok(Self {
+            public_http_client: Self::build_http_client(header::HeaderMap::new(), &options)?,
+            http_client,
+            fallback_base_url: Self::normalized_fallback_base_url(&options)?,
+            base_url,
+            default_headers: headers,
+            rate_limiter: Self::build_rate_limiter(&options),
+            metrics: Arc::default(),
+            team_visibility: Arc::default(),
+            note_list_cache: Arc::default(),
+            #[cfg(feature = "latency")]
+            latency: Arc::default(),
+            options,
+            single_flight: Arc::default(),
+            #[cfg(feature = "request-id")]
+            last_request_id: Arc::default(),
+        })
+    }
+
+    /// Async counterpart of `with_options`. When `verify_on_build` is set, it calls
+    /// `get_me` before returning so an invalid token fails at startup.
+    pub async fn connect(
+        access_token: &str,
+        base_url: Option<&str>,
+        options: Option<ApiClientOptions>,
+    ) -> Result<Self> {
+        let client = Self::with_options(access_token, base_url, options)?;
+        if client.options.verify_on_build {
+            client.get_me().await?;
+        }
+        Ok(client)
+    }
 
+    fn auth_headers(access_token: &str) -> Result<header::HeaderMap> {
         let mut headers = header::HeaderMap::new();
         headers.insert(
             header::AUTHORIZATION,
             header::HeaderValue::from_str(&format!("Bearer {}", access_token))?,
         );
+        Ok(headers)
+    }
 
+    fn build_http_client(
+        headers: header::HeaderMap,
+        options: &ApiClientOptions,
+    ) -> Result<HttpClient> {
         let mut client_builder = HttpClient::builder().default_headers(headers);
 
         if let Some(timeout) = options.timeout {
             client_builder = client_builder.timeout(timeout);
         }
 
-        let http_client = client_builder.build()?;
-        let base_url = Url::parse(&Self::normalized_base_url(
-            base_url.unwrap_or(DEFAULT_BASE_URL),
-        ))?;
-
-        Ok(Self {
-            http_client,
-            base_url,
-            options,
-        })
-    }
+        if let Some(connect_timeout) = options.connect_timeout {
+            client_builder = client_builder.connect_timeout(connect_timeout);
+        }
 
-    async fn handle_response<T>(&self, response: Response) -> Result<T>
-    where
-        T: serde::de::DeserializeOwned,
-    {
-        let status = response.status();
+        if options.accept_invalid_certs {
+            client_builder = client_builder.danger_accept_invalid_certs(true);
+        }
 
-        if !self.options.wrap_response_errors {
-            return if status.is_success() {
-                Ok(response.json().await?)
-            } else {
-                Err(ApiError::Reqwest(response.error_for_status().unwrap_err()))
-            };
+        if let Some(local_address) = options.local_address {
+            client_builder = client_builder.local_address(local_address);
         }
 
-        if status.is_success() {
-            return Ok(response.json().await?);
-        }
-
-        let status_text = status.canonical_reason().unwrap_or("Unknown").to_string();
-        let user_limit = response
-            .headers()
-            .get("x-ratelimit-userlimit")
-            .and_then(|v| v.to_str().ok())
-            .and_then(|v| v.parse().ok())
-            .unwrap_or(0);
-        let user_remaining = response
-            .headers()
-            .get("x-ratelimit-userremaining")
-            .and_then(|v| v.to_str().ok())
-            .and_then(|v| v.parse().ok())
-            .unwrap_or(0);
-        let reset_after = response
-            .headers()
-            .get("x-ratelimit-userreset")
-            .and_then(|v| v.to_str().ok())
-            .and_then(|v| v.parse().ok());
-        let error_body = response.text().await.unwrap_or_default();
-        let error_detail = if error_body.trim().is_empty() {
-            String::new()
-        } else {
-            format!(": {}", error_body.trim())
-        };
+        if options.http2_prior_knowledge {
+            client_builder = client_builder.http2_prior_knowledge();
+        }
 
-        match status {
-            StatusCode::TOO_MANY_REQUESTS => Err(ApiError::TooManyRequests(TooManyRequestsError {
-                message: format!(
-                    "Too many requests ({} {}){}",
-                    status.as_u16(),
-                    status_text,
-                    error_detail
-                ),
-                code: status.as_u16(),
-                status_text,
-                user_limit,
-                user_remaining,
-                reset_after,
-            })),
-            _ if status.is_server_error() => Err(ApiError::InternalServer(InternalServerError {
-                message: format!(
-                    "HackMD internal error ({} {}){}",
-                    status.as_u16(),
-                    status_text,
-                    error_detail
-                ),
-                code: status.as_u16(),
-                status_text,
-            })),
-            _ => Err(ApiError::HttpResponse(HttpResponseError {
-                message: format!(
-                    "Received an error response ({} {}) from HackMD{}",
-                    status.as_u16(),
-                    status_text,
-                    error_detail
-                ),
-                code: status.as_u16(),
-                status_text,
-            })),
+        #[cfg(feature = "cookies")]
+        if options.cookie_store {
+            client_builder = client_builder.cookie_store(true);
         }
+
+        Ok(client_builder.build()?)
     }
 
-    async fn handle_empty_response(&self, response: Response) -> Result<()> {
-        if Self::is_success_status(response.status()) {
-            return Ok(());
+    fn build_rate_limiter(options: &ApiClientOptions) -> Option<Arc<RateLimiter>> {
+        options
+            .rate_limit
+            .map(|rate_limit| Arc::new(RateLimiter::new(rate_limit.max_requests, rate_limit.per)))
+    }
+
+    async fn start_attempt(&self) {
+        if let Some(rate_limiter) = &self.rate_limiter {
+            rate_limiter.acquire().await;
         }
+        self.metrics.total_requests.fetch_add(1, Ordering::Relaxed);
+    }
 
-        self.handle_response::<Value>(response).await.map(|_| ())
+    pub fn metrics(&self) -> RetryMetricsSnapshot {
+        self.metrics.snapshot()
     }
 
-    async fn retry_request<F, Fut, T>(&self, operation: F) -> Result<T>
-    where
-        F: Fn() -> Fut,
-        Fut: future::Future<Output = Result<T>>,
-    {
-        let retry_options = match &self.options.retry_options {
-            Some(config) => config,
-            None => return operation().await,
-        };
+    pub fn with_updated_options(&self, options: ApiClientOptions) -> Result<Self> {
+        options.validate()?;
 
-        let mut last_error = None;
-        for attempt in 0..=retry_options.max_retries {
-            match operation().await {
-                Ok(result) => return Ok(result),
-                Err(err) => {
-                    if attempt < retry_options.max_retries && self.is_retryable_error(&err) {
-                        let delay = self.exponential_backoff(attempt, retry_options.base_delay);
-                        tokio::time::sleep(delay).await;
-                        last_error = Some(err);
-                    } else {
-                        return Err(err);
-                    }
-                }
-            }
-        }
+        let rate_limiter = if options.rate_limit == self.options.rate_limit {
+            self.rate_limiter.clone()
+        } else {
+            Self::build_rate_limiter(&options)
+        };
 
-        Err(last_error.unwrap())
+        Ok(Self {
+            http_client: Self::build_http_client(self.default_headers.clone(), &options)?,
+            public_http_client: Self::build_http_client(header::HeaderMap::new(), &options)?,
+            rate_limiter,
+            metrics: Arc::clone(&self.metrics),
+            team_visibility: Arc::clone(&self.team_visibility),
+            note_list_cache: Arc::clone(&self.note_list_cache),
+            #[cfg(feature = "latency")]
+            latency: Arc::clone(&self.latency),
+            base_url: self.base_url.clone(),
+            fallback_base_url: Self::normalized_fallback_base_url(&options)?,
+            default_headers: self.default_headers.clone(),
+            options,
+            single_flight: Arc::clone(&self.single_flight),
+            #[cfg(feature = "request-id")]
+            last_request_id: Arc::default(),
+        })
     }
 
-    fn is_retryable_error(&self, error: &ApiError) -> bool {
-        match error {
-            ApiError::TooManyRequests(_) => true,
-            ApiError::InternalServer(_) => true,
-            ApiError::Reqwest(req_err) => {
-                req_err.is_timeout() || req_err.is_connect() || req_err.is_request()
-            }
-            _ => false,
+    /// Replaces the access token used by this client. Requests sent afterwards,
+    /// including `get_me`, use the new token immediately; nothing is cached
+    /// across the swap. Clones made earlier keep their original token.
+    pub fn set_access_token(&mut self, access_token: &str) -> Result<()> {
+        if access_token.trim().is_empty() {
+            return Err(Self::missing_required_argument(
+                "Missing access token when updating HackMD client",
+            ));
         }
+
+        let headers = Self::auth_headers(access_token)?;
+        self.http_client = Self::build_http_client(headers.clone(), &self.options)?;
+        self.default_headers = headers;
+        // In-flight GETs are coalesced by URL only, so stop sharing them with
+        // clones that still hold the previous token.
+        self.single_flight = Arc::default();
+        self.team_visibility = Arc::default();
+        self.note_list_cache = Arc::default();
+        Ok(())
     }
 
-    fn exponential_backoff(&self, retries: u32, base_delay: time::Duration) -> time::Duration {
-        let multiplier = 2_u64.pow(retries);
-        time::Duration::from_millis(base_delay.as_millis() as u64 * multiplier)
+    #[cfg(feature = "request-id")]
+    pub fn last_request_id(&self) -> Option<String> {
+        self.last_request_id
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
     }
 
-    pub async fn get_me(&self) -> Result<User> {
-        self.retry_request(|| async {
-            let url = self.base_url.join("me")?;
-            let response = self.http_client.get(url).send().await?;
-            self.handle_response(response).await
-        })
-        .await
+    fn note_list_cache(&self) -> MutexGuard<'_, Option<NoteListCache>> {
+        self.note_list_cache
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
     }
 
-    pub async fn get_history(&self, limit: Option<u32>) -> Result<Vec<Note>> {
-        self.retry_request(|| async {
-            let mut url = self.base_url.join("history")?;
-            if let Some(limit_val) = limit {
-                url.query_pairs_mut()
-                    .append_pair("limit", &limit_val.to_string());
-            }
-            let response = self.http_client.get(url).send().await?;
-            self.handle_response(response).await
-        })
-        .await
+    fn request(&self, method: Method, url: Url) -> RequestBuilder {
+        let request = self.http_client.request(method, url);
+
+        #[cfg(feature = "request-id")]
+        let request = {
+            let request_id = uuid::Uuid::new_v4().to_string();
+            let request = request.header("x-request-id", &request_id);
+            *self
+                .last_request_id
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(request_id);
+            request
+        };
+
+        request
     }
 
-    pub async fn get_note_list(&self) -> Result<Vec<Note>> {
-        self.retry_request(|| async {
-            let url = self.base_url.join("notes")?;
-            let response = self.http_client.get(url).send().await?;
-            self.handle_response(response).await
-        })
-        .await
+    /// Builds a request on a client without the `Authorization` header, for public pages
+    /// such as a note's `publish_link`.
+    fn public_request(&self, method: Method, url: Url) -> RequestBuilder {
+        self.public_http_client.request(method, url)
     }
 
-    pub async fn get_note(&self, note_id: &str) -> Result<SingleNote> {
-        self.retry_request(|| async {
-            let url = self.note_url(note_id)?;
-            let response = self.http_client.get(url).send().await?;
-            self.handle_response(response).await
-        })
-        .await
+    pub fn http_client(&self) -> &HttpClient {
+        &self.http_client
     }
 
-    pub async fn create_note(&self, payload: &CreateNoteOptions) -> Result<SingleNote> {
-        self.retry_request(|| async {
-            let url = self.base_url.join("notes")?;
-            let response = self.http_client.post(url).json(payload).send().await?;
-            self.handle_response(response).await
-        })
-        .await
+    pub fn base_url(&self) -> &Url {
+        &self.base_url
     }
 
-    pub async fn create_note_content(&self, content: &str) -> Result<SingleNote> {
-        self.retry_request(|| async {
-            let url = self.base_url.join("notes")?;
-            let response = self.http_client.post(url).json(&content).send().await?;
-            self.handle_response(response).await
+    fn validation_error(message: impl Into<String>) -> ApiError {
+        ApiError::Validation(ValidationError {
+            message: message.into(),
         })
-        .await
     }
 
-    pub async fn update_note_content(&self, note_id: &str, content: &str) -> Result<()> {
-        let payload = UpdateNoteOptions {
-            content: Some(content.to_string()),
-            ..Default::default()
+    async fn read_limited_body(&self, mut response: Response, max_bytes: usize) -> Result<Vec<u8>> {
+        let too_large = || {
+            Self::validation_error(format!(
+                "HackMD response body exceeds the {max_bytes} byte limit"
+            ))
         };
-        self.update_note(note_id, &payload).await
-    }
 
-    pub async fn update_note(&self, note_id: &str, payload: &UpdateNoteOptions) -> Result<()> {
-        self.retry_request(|| async {
-            let url = self.note_url(note_id)?;
-            let response = self.http_client.patch(url).json(payload).send().await?;
-            self.handle_empty_response(response).await
+        if response
+            .content_length()
+            .is_some_and(|length| length > max_bytes as u64)
+        {
+            return Err(too_large());
+        }
+
+        let mut body = Vec::new();
+        while let Some(chunk) = response.chunk().await? {
+            if body.len() + chunk.len() > max_bytes {
+                return Err(too_large());
+            }
+            body.extend_from_slice(&chunk);
+        }
+
+        Ok(body)
+    }
+
+    async fn read_json<T>(&self, response: Response) -> Result<T>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        match self.options.max_response_bytes {
+            Some(max_bytes) => {
+                let body = self.read_limited_body(response, max_bytes).await?;
+                Ok(serde_json::from_slice(&body)?)
+            }
+            None => Ok(response.json().await?),
+        }
+    }
+
+    async fn read_success_json<T>(&self, response: Response) -> Result<T>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        if !self.options.unwrap_data_envelope {
+            return self.read_json(response).await;
+        }
+
+        let body: Value = self.read_json(response).await?;
+        Self::unwrap_data_envelope(body)
+    }
+
+    fn unwrap_data_envelope<T>(body: Value) -> Result<T>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        if let Some(Ok(data)) = body.get("data").map(T::deserialize) {
+            return Ok(data);
+        }
+        Ok(serde_json::from_value(body)?)
+    }
+
+    async fn read_text(&self, response: Response) -> Result<String> {
+        match self.options.max_response_bytes {
+            Some(max_bytes) => {
+                let body = self.read_limited_body(response, max_bytes).await?;
+                Ok(String::from_utf8_lossy(&body).into_owned())
+            }
+            None => Ok(response.text().await?),
+        }
+    }
+
+    async fn execute(&self, request: RequestBuilder) -> Result<Response> {
+        let (client, request) = request.build_split();
+        let request = request?;
+        #[cfg(feature = "latency")]
+        let (endpoint, started) = (
+            self.endpoint_key(request.method(), request.url()),
+            time::Instant::now(),
+        );
+
+        let response = client.execute(request).await?;
+
+        #[cfg(feature = "latency")]
+        self.latency.record(endpoint, started.elapsed());
+        Ok(response)
+    }
+
+    /// Labels a request as `METHOD path` relative to the base URL, with identifier segments
+    /// replaced by `:id` so that every note or team shares one histogram.
+    #[cfg(feature = "latency")]
+    fn endpoint_key(&self, method: &Method, url: &Url) -> String {
+        let base_path = self.base_url.path().trim_end_matches('/');
+        let path = url.path().strip_prefix(base_path).unwrap_or(url.path());
+        let segments: Vec<&str> = path
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .enumerate()
+            .map(|(index, segment)| match segment {
+                "folder-order" => segment,
+                _ if index % 2 == 1 => ":id",
+                _ => segment,
+            })
+            .collect();
+        format!("{method} {}", segments.join("/"))
+    }
+
+    #[cfg(feature = "latency")]
+    pub fn latency_snapshot(&self) -> HashMap<String, LatencySnapshot> {
+        self.latency.snapshot()
+    }
+
+    async fn send_get(&self, url: Url) -> Result<Response> {
+        self.send_conditional_get(url, None).await
+    }
+
+    /// `send_get` with an optional `If-None-Match` header. Conditional requests are only
+    /// coalesced with others carrying the same `ETag`.
+    async fn send_conditional_get(&self, url: Url, etag: Option<&str>) -> Result<Response> {
+        let request = || {
+            let request = self.request(Method::GET, url.clone());
+            match etag {
+                Some(etag) => request.header(header::IF_NONE_MATCH, etag),
+                None => request,
+            }
+        };
+        if !self.options.single_flight {
+            return self.execute(request()).await;
+        }
+
+        let mut key = url.to_string();
+        if let Some(etag) = etag {
+            key = format!("{key} if-none-match={etag}");
+        }
+        match self.single_flight.join(key) {
+            Flight::Follower(receiver) => match receiver.await {
+                Ok(Some(response)) => Ok(response.into_response()),
+                _ => self.execute(request()).await,
+            },
+            Flight::Leader(guard) => {
+                let result = self.fetch_buffered(request()).await;
+                guard.complete(result.as_ref().ok().cloned());
+                result.map(BufferedResponse::into_response)
+            }
+        }
+    }
+
+    async fn fetch_buffered(&self, request: RequestBuilder) -> Result<BufferedResponse> {
+        let response = self.execute(request).await?;
+        let status = response.status();
+        let headers = response.headers().clone();
+        let body = match self.options.max_response_bytes {
+            Some(max_bytes) => self.read_limited_body(response, max_bytes).await?.into(),
+            None => response.bytes().await?,
+        };
+
+        Ok(BufferedResponse {
+            status,
+            headers,
+            body,
+        })
+    }
+
+    async fn handle_response<T>(&self, response: Response) -> Result<T>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        let status = response.status();
+        self.metrics.record_status(status);
+
+        if !self.options.wrap_response_errors {
+            return if status.is_success() {
+                self.read_success_json(response).await
+            } else {
+                Err(ApiError::Reqwest(response.error_for_status().unwrap_err()))
+            };
+        }
+
+        if status.is_success() {
+            return self.read_success_json(response).await;
+        }
+
+        let headers = response.headers().clone();
+        let error_body = self.read_text(response).await.unwrap_or_default();
+        Err(map_status_error(status, &headers, &error_body))
+    }
+
+    async fn handle_empty_response(&self, response: Response) -> Result<()> {
+        if Self::is_success_status(response.status()) {
+            return Ok(());
+        }
+
+        self.handle_response::<Value>(response).await.map(|_| ())
+    }
+
+    /// Runs `operation` under the retry policy for `endpoint`. Each attempt is handed the base
+    /// URL to build its request against: `base_url`, or `fallback_base_url` on failover.
+    async fn retry_request<'a, F, Fut, T>(
+        &'a self,
+        endpoint: &'static str,
+        operation: F,
+    ) -> Result<T>
+    where
+        F: Fn(&'a Url) -> Fut,
+        Fut: future::Future<Output = Result<T>>,
+    {
+        self.retry_request_with_stats(endpoint, operation)
+            .await
+            .map(|(result, _)| result)
+    }
+
+    /// The retry policy for `endpoint`: its `endpoint_retry_overrides` entry if there is one,
+    /// otherwise `retry_options`.
+    fn retry_options_for(&self, endpoint: &str) -> Option<&RetryOptions> {
+        match self.options.endpoint_retry_overrides.get(endpoint) {
+            Some(retry_options) => retry_options.as_ref(),
+            None => self.options.retry_options.as_ref(),
+        }
+    }
+
+    async fn retry_non_idempotent_request<'a, F, Fut, T>(
+        &'a self,
+        endpoint: &'static str,
+        operation: F,
+    ) -> Result<T>
+    where
+        F: Fn(&'a Url) -> Fut,
+        Fut: future::Future<Output = Result<T>>,
+    {
+        let retry_non_idempotent = self
+            .retry_options_for(endpoint)
+            .is_some_and(|retry_options| retry_options.retry_non_idempotent);
+        if retry_non_idempotent {
+            return self.retry_request(endpoint, operation).await;
+        }
+
+        let attempt = async {
+            self.start_attempt().await;
+            operation(&self.base_url).await
+        };
+        match self.options.overall_timeout {
+            Some(total) => self.wrap_with_timeout(total, attempt).await,
+            None => attempt.await,
+        }
+    }
+
+    async fn retry_request_with_stats<'a, F, Fut, T>(
+        &'a self,
+        endpoint: &'static str,
+        operation: F,
+    ) -> Result<(T, RequestStats)>
+    where
+        F: Fn(&'a Url) -> Fut,
+        Fut: future::Future<Output = Result<T>>,
+    {
+        let retry_loop = self.retry_loop(self.retry_options_for(endpoint), operation);
+        match self.options.overall_timeout {
+            Some(total) => self.wrap_with_timeout(total, retry_loop).await,
+            None => retry_loop.await,
+        }
+    }
+
+    async fn wrap_with_timeout<Fut, T>(&self, total: time::Duration, operation: Fut) -> Result<T>
+    where
+        Fut: future::Future<Output = Result<T>>,
+    {
+        tokio::time::timeout(total, operation)
+            .await
+            .unwrap_or_else(|_| {
+                Err(ApiError::Timeout(TimeoutError {
+                    message: "HackMD request did not complete within the overall timeout"
+                        .to_string(),
+                    timeout: total,
+                }))
+            })
+    }
+
+    async fn retry_loop<'a, F, Fut, T>(
+        &'a self,
+        retry_options: Option<&RetryOptions>,
+        operation: F,
+    ) -> Result<(T, RequestStats)>
+    where
+        F: Fn(&'a Url) -> Fut,
+        Fut: future::Future<Output = Result<T>>,
+    {
+        let mut stats = RequestStats::default();
+        let Some(config) = retry_options else {
+            stats.attempts = 1;
+            self.start_attempt().await;
+            return match operation(&self.base_url).await {
+                Ok(result) => Ok((result, stats)),
+                Err(err) => self.fail_over(None, &operation, err, stats).await,
+            };
+        };
+
+        let started = time::Instant::now();
+        let mut last_error = None;
+        for attempt in 0..=config.max_retries {
+            stats.attempts += 1;
+            self.start_attempt().await;
+            match operation(&self.base_url).await {
+                Ok(result) => return Ok((result, stats)),
+                Err(err) => {
+                    let remaining = self
+                        .options
+                        .overall_timeout
+                        .map(|total| total.saturating_sub(started.elapsed()));
+                    let delay = if attempt < config.max_retries
+                        && Self::is_retryable_error(retry_options, &err)
+                    {
+                        Self::retry_delay(config, attempt, &err, remaining)
+                    } else {
+                        None
+                    };
+                    match delay {
+                        Some(delay) => {
+                            tokio::time::sleep(delay).await;
+                            stats.total_wait += delay;
+                            self.metrics.total_retries.fetch_add(1, Ordering::Relaxed);
+                            last_error = Some(err);
+                        }
+                        None => return self.fail_over(retry_options, &operation, err, stats).await,
+                    }
+                }
+            }
+        }
+
+        Err(last_error.unwrap())
+    }
+
+    /// Makes one more attempt against `fallback_base_url` after a retryable failure.
+    async fn fail_over<'a, F, Fut, T>(
+        &'a self,
+        retry_options: Option<&RetryOptions>,
+        operation: &F,
+        error: ApiError,
+        mut stats: RequestStats,
+    ) -> Result<(T, RequestStats)>
+    where
+        F: Fn(&'a Url) -> Fut,
+        Fut: future::Future<Output = Result<T>>,
+    {
+        let Some(fallback_base_url) = &self.fallback_base_url else {
+            return Err(error);
+        };
+        if !Self::is_retryable_error(retry_options, &error) {
+            return Err(error);
+        }
+
+        stats.attempts += 1;
+        self.start_attempt().await;
+        operation(fallback_base_url)
+            .await
+            .map(|result| (result, stats))
+    }
+
+    fn is_retryable_error(retry_options: Option<&RetryOptions>, error: &ApiError) -> bool {
+        if let Some(retry_predicate) =
+            retry_options.and_then(|retry_options| retry_options.retry_predicate.as_ref())
+        {
+            return retry_predicate(error);
+        }
+
+        let retry_decode_errors =
+            retry_options.is_some_and(|retry_options| retry_options.retry_decode_errors);
+
+        match error {
+            ApiError::TooManyRequests(_) => true,
+            ApiError::InternalServer(_) => true,
+            ApiError::Reqwest(req_err) => {
+                req_err.is_timeout()
+                    || req_err.is_connect()
+                    || req_err.is_request()
+                    || (retry_decode_errors && req_err.is_decode())
+            }
+            ApiError::Serde(_) => retry_decode_errors,
+            _ => false,
+        }
+    }
+
+    /// How long to wait before retrying after `error`. Returns `None` when a rate-limit reset
+    /// is further away than `MAX_RATE_LIMIT_WAIT` or the `remaining` overall timeout, so the
+    /// 429 is returned right away instead of blocking until the reset.
+    fn retry_delay(
+        retry_options: &RetryOptions,
+        attempt: u32,
+        error: &ApiError,
+        remaining: Option<time::Duration>,
+    ) -> Option<time::Duration> {
+        let backoff = Self::exponential_backoff(retry_options, attempt);
+        match error {
+            ApiError::TooManyRequests(err) => match err.retry_after() {
+                Some(retry_after) => {
+                    let max_wait = remaining.map_or(MAX_RATE_LIMIT_WAIT, |remaining| {
+                        remaining.min(MAX_RATE_LIMIT_WAIT)
+                    });
+                    (retry_after <= max_wait).then(|| retry_after.max(backoff))
+                }
+                None => Some(backoff),
+            },
+            _ => Some(backoff),
+        }
+    }
+
+    fn exponential_backoff(retry_options: &RetryOptions, retries: u32) -> time::Duration {
+        let millis = retry_options.base_delay.as_millis() as f64
+            * retry_options.multiplier.powf(f64::from(retries));
+
+        if millis.is_finite() {
+            time::Duration::from_millis(millis.round() as u64).min(MAX_BACKOFF_DELAY)
+        } else {
+            MAX_BACKOFF_DELAY
+        }
+    }
+
+    pub async fn get_me(&self) -> Result<User> {
+        self.retry_request("get_me", |base_url| async move {
+            let url = base_url.join("me")?;
+            let response = self.send_get(url).await?;
+            self.handle_response(response).await
         })
         .await
     }
 
-    pub async fn delete_note(&self, note_id: &str) -> Result<()> {
-        self.retry_request(|| async {
-            let url = self.note_url(note_id)?;
-            let response = self.http_client.delete(url).send().await?;
-            self.handle_empty_response(response).await
+    pub async fn get_me_with_stats(&self) -> Result<(User, RequestStats)> {
+        self.retry_request_with_stats("get_me", |base_url| async move {
+            let url = base_url.join("me")?;
+            let response = self.send_get(url).await?;
+            self.handle_response(response).await
         })
         .await
     }
 
-    pub async fn upload_note_image(
-        &self,
-        note_id: &str,
-        image_bytes: bytes::Bytes,
-        file_name: &str,
-        mime_type: &str,
-    ) -> Result<NoteImageUploadResponse> {
-        self.retry_request(|| async {
-            let url = self.note_image_url(note_id)?;
-            let part = reqwest::multipart::Part::stream(image_bytes.clone())
-                .file_name(file_name.to_string())
-                .mime_str(mime_type)?;
-            let form = reqwest::multipart::Form::new().part("image", part);
-            let response = self.http_client.post(url).multipart(form).send().await?;
+    /// Returns notes the user recently viewed, which may include notes they do not own.
+    pub async fn get_history(&self, limit: Option<u32>) -> Result<Vec<Note>> {
+        self.retry_request("get_history", |base_url| async move {
+            let mut url = base_url.join("history")?;
+            if let Some(limit_val) = limit {
+                url.query_pairs_mut()
+                    .append_pair("limit", &limit_val.to_string());
+            }
+            let response = self.send_get(url).await?;
             self.handle_response(response).await
         })
         .await
     }
 
-    pub async fn get_folders(&self) -> Result<Vec<Folder>> {
-        self.retry_request(|| async {
-            let url = self.folders_url()?;
-            let response = self.http_client.get(url).send().await?;
+    /// Polls history every `poll_interval` and yields the notes changed since the previous
+    /// poll; the first item holds the whole history. Ticks without changes yield nothing,
+    /// and a failed poll yields the error without ending the stream.
+    #[cfg(feature = "stream")]
+    pub fn watch_notes(
+        &self,
+        poll_interval: time::Duration,
+    ) -> impl futures_util::Stream<Item = Result<Vec<Note>>> + '_ {
+        futures_util::stream::unfold(
+            (None, true),
+            move |(mut last_seen, mut first): (Option<chrono::DateTime<chrono::Utc>>, bool)| async move {
+                loop {
+                    if !first {
+                        tokio::time::sleep(poll_interval).await;
+                    }
+                    first = false;
+
+                    let notes = match self.get_history(None).await {
+                        Ok(notes) => notes,
+                        Err(error) => return Some((Err(error), (last_seen, first))),
+                    };
+                    let changed: Vec<Note> = notes
+                        .into_iter()
+                        .filter(|note| last_seen.is_none_or(|seen| note.last_changed_at > seen))
+                        .collect();
+                    if let Some(newest) = changed.iter().map(|note| note.last_changed_at).max() {
+                        last_seen = Some(newest);
+                        return Some((Ok(changed), (last_seen, first)));
+                    }
+                }
+            },
+        )
+    }
+
+    /// Requests one page of history with `offset` and `limit` query parameters.
+    /// If the server ignores them and returns more than `limit` notes, the page is
+    /// sliced client-side from the full history instead. A response of at most `limit`
+    /// notes is trusted as the requested page.
+    pub async fn get_history_paginated(&self, offset: usize, limit: usize) -> Result<Vec<Note>> {
+        let notes: Vec<Note> = self
+            .retry_request("get_history", |base_url| async move {
+                let mut url = base_url.join("history")?;
+                url.query_pairs_mut()
+                    .append_pair("offset", &offset.to_string())
+                    .append_pair("limit", &limit.to_string());
+                let response = self.send_get(url).await?;
+                self.handle_response(response).await
+            })
+            .await?;
+
+        if notes.len() <= limit {
+            return Ok(notes);
+        }
+
+        Ok(notes.into_iter().skip(offset).take(limit).collect())
+    }
+
+    /// Returns the notes owned by the user, regardless of when they were last viewed.
+    ///
+    /// When the previous response carried an `ETag`, the request sends `If-None-Match` and a
+    /// `304 Not Modified` answer is served from the cached list without downloading it again.
+    pub async fn get_note_list(&self) -> Result<Vec<Note>> {
+        self.retry_request("get_note_list", |base_url| async move {
+            let url = base_url.join("notes")?;
+            let cached_etag = self
+                .note_list_cache()
+                .as_ref()
+                .map(|cache| cache.etag.clone());
+            let mut response = self
+                .send_conditional_get(url.clone(), cached_etag.as_deref())
+                .await?;
+
+            if response.status() == StatusCode::NOT_MODIFIED {
+                if let Some(cache) = self.note_list_cache().as_ref() {
+                    return Ok(cache.notes.clone());
+                }
+                // The cache was reset while the request was in flight; fetch the full list.
+                response = self.send_get(url).await?;
+            }
+
+            let etag = response
+                .headers()
+                .get(header::ETAG)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string);
+            let NoteListBody(notes) = self.handle_response(response).await?;
+            *self.note_list_cache() = etag.map(|etag| NoteListCache {
+                etag,
+                notes: notes.clone(),
+            });
+            Ok(notes)
+        })
+        .await
+    }
+
+    /// Returns the note titled `title`, creating it from `default` when none exists. When
+    /// several notes share the title, the most recently changed one wins. This is not atomic:
+    /// two concurrent callers can both miss and create duplicates.
+    pub async fn get_or_create_by_title(
+        &self,
+        title: &str,
+        default: &CreateNoteOptions,
+    ) -> Result<SingleNote> {
+        Self::require_non_empty("title", title)?;
+
+        let existing = self
+            .get_note_list()
+            .await?
+            .into_iter()
+            .filter(|note| note.title == title)
+            .max_by_key(|note| note.last_changed_at);
+        if let Some(note) = existing {
+            return self.get_note(&note.id).await;
+        }
+
+        self.create_note(&CreateNoteOptions {
+            title: Some(title.to_string()),
+            ..default.clone()
+        })
+        .await
+    }
+
+    pub async fn get_writable_notes(&self) -> Result<Vec<Note>> {
+        let user = self.get_me().await?;
+        let notes = self.get_note_list().await?;
+        Ok(notes
+            .into_iter()
+            .filter(|note| note.is_writable_by(&user))
+            .collect())
+    }
+
+    pub async fn get_notes_by_publish_type(
+        &self,
+        publish_type: NotePublishType,
+    ) -> Result<Vec<Note>> {
+        let notes = self.get_note_list().await?;
+        Ok(notes
+            .into_iter()
+            .filter(|note| note.publish_type == publish_type)
+            .collect())
+    }
+
+    fn merge_recent_notes(history: Vec<Note>, owned: Vec<Note>, limit: usize) -> Vec<Note> {
+        let mut seen = HashSet::new();
+        let mut notes: Vec<Note> = history
+            .into_iter()
+            .chain(owned)
+            .filter(|note| seen.insert(note.id.clone()))
+            .collect();
+        notes.sort_by_key(|note| std::cmp::Reverse(note.last_changed_at));
+        notes.truncate(limit);
+        notes
+    }
+
+    pub async fn get_recent_notes(&self, limit: usize) -> Result<Vec<Note>> {
+        let history = self.get_history(None).await?;
+        let owned = self.get_note_list().await?;
+        Ok(Self::merge_recent_notes(history, owned, limit))
+    }
+
+    pub async fn list_notes_paged(&self, page_size: usize) -> Result<NoteList> {
+        if page_size == 0 {
+            return Err(Self::missing_required_argument(
+                "Missing non-zero page_size when listing HackMD notes",
+            ));
+        }
+
+        let notes = self.get_note_list().await?;
+        Ok(NoteList::new(notes, page_size))
+    }
+
+    /// Fetches a note by its full `id`, not its `short_id`.
+    /// Use [`ApiClient::get_note_by_short_id`] when only the short ID is known.
+    pub async fn get_note(&self, note_id: &str) -> Result<SingleNote> {
+        self.retry_request("get_note", |base_url| async move {
+            let url = Self::note_url(base_url, note_id)?;
+            let response = self.send_get(url).await?;
             self.handle_response(response).await
         })
         .await
     }
 
-    pub async fn create_folder(&self, payload: &CreateFolderOptions) -> Result<Folder> {
-        self.retry_request(|| async {
-            let url = self.folders_url()?;
-            let response = self.http_client.post(url).json(payload).send().await?;
+    pub async fn get_note_with_headers(&self, note_id: &str) -> Result<(SingleNote, ResponseMeta)> {
+        self.retry_request("get_note", |base_url| async move {
+            let url = Self::note_url(base_url, note_id)?;
+            let response = self.send_get(url).await?;
+            let meta = ResponseMeta::from_headers(response.headers());
+            Ok((self.handle_response(response).await?, meta))
+        })
+        .await
+    }
+
+    /// Sums the UTF-8 byte length of every note's content from `get_note_list()`, fetching
+    /// four notes at a time. All or nothing: the first failed fetch is returned as the error,
+    /// and a panic in a fetch task is resumed on the caller.
+    pub async fn total_content_bytes(&self) -> Result<usize> {
+        let notes = self.get_note_list().await?;
+        let permits = Arc::new(tokio::sync::Semaphore::new(MAX_CONCURRENT_REQUESTS));
+        let mut fetches = tokio::task::JoinSet::new();
+
+        for note in notes {
+            let client = self.clone();
+            let permits = Arc::clone(&permits);
+            fetches.spawn(async move {
+                let _permit = permits.acquire_owned().await;
+                client
+                    .get_note(&note.id)
+                    .await
+                    .map(|note| note.content.len())
+            });
+        }
+
+        let mut total = 0;
+        while let Some(joined) = fetches.join_next().await {
+            total += joined.map_err(Self::join_error)??;
+        }
+        Ok(total)
+    }
+
+    /// Fetches the rendered page at a published note's `publish_link`. The access token is
+    /// not sent, since published notes are public and some gateways reject unexpected auth.
+    pub async fn get_published_html(&self, publish_link: &str) -> Result<String> {
+        Self::require_non_empty("publish_link", publish_link)?;
+        let url = &Url::parse(publish_link)?;
+
+        // The publish link is absolute, so it is fetched as-is rather than under a base URL.
+        self.retry_request("get_published_html", |_| async move {
+            let response = self
+                .execute(self.public_request(Method::GET, url.clone()))
+                .await?;
+            if !response.status().is_success() {
+                return self
+                    .handle_response::<Value>(response)
+                    .await
+                    .map(|_| String::new());
+            }
+            self.read_text(response).await
+        })
+        .await
+    }
+
+    pub async fn get_note_by_short_id(&self, short_id: &str) -> Result<SingleNote> {
+        Self::require_non_empty("short_id", short_id)?;
+
+        let notes = self.get_note_list().await?;
+        let note = notes
+            .iter()
+            .find(|note| note.short_id == short_id)
+            .ok_or_else(|| {
+                ApiError::HackMD(HackMDError {
+                    message: format!("No note with short_id {short_id} in the note list"),
+                })
+            })?;
+
+        self.get_note(&note.id).await
+    }
+
+    pub async fn create_note(&self, payload: &CreateNoteOptions) -> Result<SingleNote> {
+        self.check_content(payload.content.as_deref())?;
+        let payload = self.normalized_create_payload(payload);
+        let body = &Self::json_body(&*payload)?;
+        #[cfg(feature = "tracing")]
+        Self::log_payload_size("create_note", body);
+        let idempotency_key = self.idempotency_key("create_note", &payload);
+        let idempotency_key = idempotency_key.as_deref();
+        self.retry_non_idempotent_request("create_note", |base_url| async move {
+            let url = base_url.join("notes")?;
+            let request = self
+                .request(Method::POST, url)
+                .headers(Self::idempotency_headers(idempotency_key)?);
+            let response = self.execute(Self::with_json_body(request, body)).await?;
             self.handle_response(response).await
         })
         .await
+        .map_err(|error| Self::with_suggested_permalink(error, &payload))
+    }
+
+    pub async fn create_note_content(&self, content: &str) -> Result<SingleNote> {
+        self.create_note_content_with_type(content, NoteContentType::Json)
+            .await
+    }
+
+    pub async fn create_note_content_with_type(
+        &self,
+        content: &str,
+        content_type: NoteContentType,
+    ) -> Result<SingleNote> {
+        self.check_content(Some(content))?;
+        let idempotency_key = self.generated_idempotency_key("create_note");
+        let idempotency_key = idempotency_key.as_deref();
+        self.retry_non_idempotent_request("create_note", |base_url| async move {
+            let url = base_url.join("notes")?;
+            let request = self
+                .request(Method::POST, url)
+                .headers(Self::idempotency_headers(idempotency_key)?);
+            let request = self.with_content_body(request, content, content_type);
+            let response = self.execute(request).await?;
+            self.handle_response(response).await
+        })
+        .await
+    }
+
+    pub async fn update_note_content(&self, note_id: &str, content: &str) -> Result<()> {
+        let payload = UpdateNoteOptions {
+            content: Some(content.to_string()),
+            ..Default::default()
+        };
+        self.update_note(note_id, &payload).await
+    }
+
+    pub async fn clear_content(&self, note_id: &str) -> Result<()> {
+        self.update_note_content(note_id, "").await
+    }
+
+    /// Reads the note, appends `text`, and writes the result back. Another writer that
+    /// updates the note between the read and the write will have its change overwritten.
+    /// The note is re-fetched after the write so the returned state is current.
+    pub async fn append_to_note(&self, note_id: &str, text: &str) -> Result<SingleNote> {
+        self.edit_note_content(note_id, |content| format!("{content}{text}"))
+            .await
+    }
+
+    /// Like `append_to_note`, but inserts `text` before the current content.
+    pub async fn prepend_to_note(&self, note_id: &str, text: &str) -> Result<SingleNote> {
+        self.edit_note_content(note_id, |content| format!("{text}{content}"))
+            .await
+    }
+
+    async fn edit_note_content(
+        &self,
+        note_id: &str,
+        edit: impl FnOnce(&str) -> String,
+    ) -> Result<SingleNote> {
+        let note = self.get_note(note_id).await?;
+        self.update_note_content(note_id, &edit(&note.content))
+            .await?;
+        self.get_note(note_id).await
+    }
+
+    pub async fn update_note_content_with_type(
+        &self,
+        note_id: &str,
+        content: &str,
+        content_type: NoteContentType,
+    ) -> Result<()> {
+        if content_type == NoteContentType::Json {
+            return self.update_note_content(note_id, content).await;
+        }
+        self.check_content(Some(content))?;
+
+        self.retry_request("update_note", |base_url| async move {
+            let url = Self::note_url(base_url, note_id)?;
+            let request =
+                self.with_content_body(self.request(Method::PATCH, url), content, content_type);
+            let response = self.execute(request).await?;
+            self.handle_empty_response(response).await
+        })
+        .await
+    }
+
+    /// `None` fields are omitted from the PATCH body and left unchanged. `content: Some("")`
+    /// is sent as an empty string and clears the note; see `clear_content`.
+    pub async fn update_note(&self, note_id: &str, payload: &UpdateNoteOptions) -> Result<()> {
+        self.check_content(payload.content.as_deref())?;
+        let payload = self.normalized_update_payload(payload);
+        let body = &Self::json_body(&*payload)?;
+        #[cfg(feature = "tracing")]
+        Self::log_payload_size("update_note", body);
+        self.retry_request("update_note", |base_url| async move {
+            let url = Self::note_url(base_url, note_id)?;
+            let request = Self::with_json_body(self.request(Method::PATCH, url), body);
+            let response = self.execute(request).await?;
+            self.handle_empty_response(response).await
+        })
+        .await
+    }
+
+    /// Applies the same patch to every note, running at most `concurrency` updates at a
+    /// time, and re-fetches each note so callers see its updated state. Results keep the
+    /// order of `note_ids`; a panic in an update task is resumed on the caller.
+    pub async fn update_notes(
+        &self,
+        note_ids: &[&str],
+        payload: &UpdateNoteOptions,
+        concurrency: usize,
+    ) -> Vec<(String, Result<SingleNote>)> {
+        self.update_notes_with_progress(note_ids, payload, concurrency, None)
+            .await
+    }
+
+    pub async fn update_notes_with_progress(
+        &self,
+        note_ids: &[&str],
+        payload: &UpdateNoteOptions,
+        concurrency: usize,
+        progress: Option<ProgressCallback>,
+    ) -> Vec<(String, Result<SingleNote>)> {
+        let permits = Arc::new(tokio::sync::Semaphore::new(concurrency.max(1)));
+        let payload = Arc::new(payload.clone());
+        let mut requests = tokio::task::JoinSet::new();
+        let mut task_indexes = HashMap::new();
+
+        for (index, note_id) in note_ids.iter().enumerate() {
+            let client = self.clone();
+            let permits = Arc::clone(&permits);
+            let payload = Arc::clone(&payload);
+            let note_id = note_id.to_string();
+            let task = requests.spawn(async move {
+                let _permit = permits.acquire_owned().await;
+                let result = match client.update_note(&note_id, &payload).await {
+                    Ok(()) => client.get_note(&note_id).await,
+                    Err(error) => Err(error),
+                };
+                (index, result)
+            });
+            task_indexes.insert(task.id(), index);
+        }
+
+        let mut results: Vec<Option<Result<SingleNote>>> = note_ids.iter().map(|_| None).collect();
+        let mut done = 0;
+        while let Some(joined) = requests.join_next().await {
+            match joined {
+                Ok((index, result)) => results[index] = Some(result),
+                Err(error) => {
+                    let index = task_indexes[&error.id()];
+                    results[index] = Some(Err(Self::join_error(error)));
+                }
+            }
+            done += 1;
+            if let Some(progress) = &progress {
+                progress(done, note_ids.len());
+            }
+        }
+
+        note_ids
+            .iter()
+            .zip(results)
+            .map(|(note_id, result)| {
+                let result = result.unwrap_or_else(|| {
+                    Err(ApiError::HackMD(HackMDError {
+                        message: format!("Update of note {note_id} did not complete"),
+                    }))
+                });
+                (note_id.to_string(), result)
+            })
+            .collect()
+    }
+
+    /// Starts a `BatchUpdater` whose worker runs on the current Tokio runtime. Fails with
+    /// `ApiError::HackMD` when called outside a runtime.
+    pub fn batch_updater(&self, debounce: time::Duration) -> Result<BatchUpdater> {
+        BatchUpdater::new(self.clone(), debounce)
+    }
+
+    /// Sends `body` as-is in a note PATCH. HackMD answers with an empty `202 Accepted`, so the
+    /// note is re-fetched afterwards to return its updated state.
+    pub async fn patch_note_raw(&self, note_id: &str, body: &Value) -> Result<SingleNote> {
+        self.retry_request("update_note", |base_url| async move {
+            let url = Self::note_url(base_url, note_id)?;
+            let response = self
+                .execute(self.request(Method::PATCH, url).json(body))
+                .await?;
+            self.handle_empty_response(response).await
+        })
+        .await?;
+        self.get_note(note_id).await
+    }
+
+    pub async fn delete_note(&self, note_id: &str) -> Result<()> {
+        self.retry_request("delete_note", |base_url| async move {
+            let url = Self::note_url(base_url, note_id)?;
+            let response = self.execute(self.request(Method::DELETE, url)).await?;
+            self.handle_empty_response(response).await
+        })
+        .await
+    }
+
+    pub async fn upload_note_image(
+        &self,
+        note_id: &str,
+        image_bytes: bytes::Bytes,
+        file_name: &str,
+        mime_type: &str,
+    ) -> Result<NoteImageUploadResponse> {
+        let image_bytes = &image_bytes;
+        self.retry_non_idempotent_request("upload_note_image", |base_url| async move {
+            let url = Self::note_image_url(base_url, note_id)?;
+            let part = reqwest::multipart::Part::stream(image_bytes.clone())
+                .file_name(file_name.to_string())
+                .mime_str(mime_type)?;
+            let form = reqwest::multipart::Form::new().part("image", part);
+            let response = self
+                .execute(self.request(Method::POST, url).multipart(form))
+                .await?;
+            self.handle_response(response).await
+        })
+        .await
+    }
+
+    pub async fn clone_note(&self, note_id: &str, new_title: Option<&str>) -> Result<SingleNote> {
+        let source = self.get_note(note_id).await?;
+        let mut payload = source.to_create_options();
+        if let Some(title) = new_title {
+            payload.title = Some(title.to_string());
+        }
+
+        self.create_note(&payload).await
+    }
+
+    pub async fn move_note_to_team(
+        &self,
+        note_id: &str,
+        team_path: &str,
+        delete_original: bool,
+    ) -> Result<SingleNote> {
+        let source = self.get_note(note_id).await?;
+        let moved = self
+            .create_team_note(team_path, &source.to_create_options())
+            .await?;
+
+        if delete_original {
+            self.delete_note(note_id).await?;
+        }
+
+        Ok(moved)
+    }
+
+    pub async fn move_team_note_to_personal(
+        &self,
+        team_path: &str,
+        note_id: &str,
+        delete_original: bool,
+    ) -> Result<SingleNote> {
+        let source = self.get_team_note(team_path, note_id).await?;
+        let moved = self.create_note(&source.to_create_options()).await?;
+
+        if delete_original {
+            self.delete_team_note(team_path, note_id).await?;
+        }
+
+        Ok(moved)
+    }
+
+    pub async fn upload_image(
+        &self,
+        note_id: &str,
+        image_bytes: Vec<u8>,
+        file_name: &str,
+        mime_type: &str,
+    ) -> Result<String> {
+        let response = self
+            .upload_note_image(note_id, image_bytes.into(), file_name, mime_type)
+            .await?;
+        Ok(response.data.link)
+    }
+
+    pub async fn get_folders(&self) -> Result<Vec<Folder>> {
+        self.retry_request("get_folders", |base_url| async move {
+            let url = Self::folders_url(base_url)?;
+            let response = self.send_get(url).await?;
+            self.handle_response(response).await
+        })
+        .await
+    }
+
+    pub async fn create_folder(&self, payload: &CreateFolderOptions) -> Result<Folder> {
+        self.retry_non_idempotent_request("create_folder", |base_url| async move {
+            let url = Self::folders_url(base_url)?;
+            let response = self
+                .execute(self.request(Method::POST, url).json(payload))
+                .await?;
+            self.handle_response(response).await
+        })
+        .await
+    }
+
+    pub async fn get_folder(&self, folder_id: &str) -> Result<Folder> {
+        self.retry_request("get_folder", |base_url| async move {
+            let url = Self::folder_url(base_url, folder_id)?;
+            let response = self.send_get(url).await?;
+            self.handle_response(response).await
+        })
+        .await
+    }
+
+    pub async fn update_folder(
+        &self,
+        folder_id: &str,
+        payload: &UpdateFolderOptions,
+    ) -> Result<()> {
+        self.retry_request("update_folder", |base_url| async move {
+            let url = Self::folder_url(base_url, folder_id)?;
+            let response = self
+                .execute(self.request(Method::PATCH, url).json(payload))
+                .await?;
+            self.handle_empty_response(response).await
+        })
+        .await
+    }
+
+    pub async fn delete_folder(&self, folder_id: &str) -> Result<()> {
+        self.retry_request("delete_folder", |base_url| async move {
+            let url = Self::folder_url(base_url, folder_id)?;
+            let response = self.execute(self.request(Method::DELETE, url)).await?;
+            self.handle_empty_response(response).await
+        })
+        .await
+    }
+
+    pub async fn get_folder_order(&self) -> Result<FolderOrder> {
+        self.retry_request("get_folder_order", |base_url| async move {
+            let url = Self::folder_order_url(base_url)?;
+            let response = self.send_get(url).await?;
+            self.handle_response(response).await
+        })
+        .await
+    }
+
+    pub async fn update_folder_order(&self, payload: &UpdateFolderOrderOptions) -> Result<()> {
+        self.retry_request("update_folder_order", |base_url| async move {
+            let url = Self::folder_order_url(base_url)?;
+            let response = self
+                .execute(self.request(Method::PUT, url).json(payload))
+                .await?;
+            self.handle_empty_response(response).await
+        })
+        .await
+    }
+
+    pub async fn get_teams(&self) -> Result<Vec<Team>> {
+        self.retry_request("get_teams", |base_url| async move {
+            let url = base_url.join("teams")?;
+            let response = self.send_get(url).await?;
+            self.handle_response(response).await
+        })
+        .await
+    }
+
+    pub fn team(&self, team_path: &str) -> TeamClient<'_> {
+        TeamClient::new(self, team_path)
+    }
+
+    pub async fn get_team_notes(&self, team_path: &str) -> Result<Vec<Note>> {
+        self.retry_request("get_team_notes", |base_url| async move {
+            let url = Self::team_notes_url(base_url, team_path)?;
+            let response = self.send_get(url).await?;
+            let NoteListBody(notes) = self.handle_response(response).await?;
+            Ok(notes)
+        })
+        .await
+    }
+
+    /// Fetches the notes of every team the user belongs to, four teams at a time. Each team
+    /// keeps its own result, so a failed fetch is reported for that team instead of looking
+    /// like an empty one. Results keep the order of `User::teams`.
+    pub async fn get_all_team_notes(&self) -> Result<Vec<(String, Result<Vec<Note>>)>> {
+        let user = self.get_me().await?;
+        let team_paths: Vec<String> = user.teams.into_iter().map(|team| team.path).collect();
+        let permits = Arc::new(tokio::sync::Semaphore::new(MAX_CONCURRENT_REQUESTS));
+        let mut requests = tokio::task::JoinSet::new();
+        let mut task_indexes = HashMap::new();
+
+        for (index, team_path) in team_paths.iter().enumerate() {
+            let client = self.clone();
+            let permits = Arc::clone(&permits);
+            let team_path = team_path.clone();
+            let task = requests.spawn(async move {
+                let _permit = permits.acquire_owned().await;
+                (index, client.get_team_notes(&team_path).await)
+            });
+            task_indexes.insert(task.id(), index);
+        }
+
+        let mut results: Vec<Option<Result<Vec<Note>>>> = team_paths.iter().map(|_| None).collect();
+        while let Some(joined) = requests.join_next().await {
+            match joined {
+                Ok((index, notes)) => results[index] = Some(notes),
+                Err(error) => {
+                    let index = task_indexes[&error.id()];
+                    results[index] = Some(Err(Self::join_error(error)));
+                }
+            }
+        }
+
+        Ok(team_paths
+            .into_iter()
+            .zip(results)
+            .map(|(team_path, notes)| {
+                let notes = notes.unwrap_or_else(|| {
+                    Err(ApiError::HackMD(HackMDError {
+                        message: format!("Fetching notes for team {team_path} did not complete"),
+                    }))
+                });
+                (team_path, notes)
+            })
+            .collect())
+    }
+
+    /// Re-raises a spawned request task's panic on the caller. A task cancelled by runtime
+    /// shutdown becomes an `ApiError::HackMD`.
+    fn join_error(error: tokio::task::JoinError) -> ApiError {
+        match error.try_into_panic() {
+            Ok(payload) => std::panic::resume_unwind(payload),
+            Err(error) => ApiError::HackMD(HackMDError {
+                message: format!("Spawned request task did not complete: {error}"),
+            }),
+        }
+    }
+
+    pub async fn get_team_notes_by_author(
+        &self,
+        team_path: &str,
+        user_path: &str,
+    ) -> Result<Vec<Note>> {
+        Self::require_non_empty("user_path", user_path)?;
+
+        let notes = self.get_team_notes(team_path).await?;
+        Ok(notes
+            .into_iter()
+            .filter(|note| note.is_last_changed_by(user_path))
+            .collect())
+    }
+
+    pub async fn get_team_note(&self, team_path: &str, note_id: &str) -> Result<SingleNote> {
+        self.retry_request("get_team_note", |base_url| async move {
+            let url = Self::team_note_url(base_url, team_path, note_id)?;
+            let response = self.send_get(url).await?;
+            self.handle_response(response).await
+        })
+        .await
+    }
+
+    pub async fn create_team_note(
+        &self,
+        team_path: &str,
+        payload: &CreateNoteOptions,
+    ) -> Result<SingleNote> {
+        self.check_content(payload.content.as_deref())?;
+        self.check_team_visibility(team_path, payload).await?;
+        let payload = self.normalized_create_payload(payload);
+        let body = &Self::json_body(&*payload)?;
+        let idempotency_key = self.idempotency_key("create_team_note", &payload);
+        let idempotency_key = idempotency_key.as_deref();
+        self.retry_non_idempotent_request("create_team_note", |base_url| async move {
+            let url = Self::team_notes_url(base_url, team_path)?;
+            let request = self
+                .request(Method::POST, url)
+                .headers(Self::idempotency_headers(idempotency_key)?);
+            let response = self.execute(Self::with_json_body(request, body)).await?;
+            self.handle_response(response).await
+        })
+        .await
+        .map_err(|error| Self::with_suggested_permalink(error, &payload))
+    }
+
+    async fn check_team_visibility(
+        &self,
+        team_path: &str,
+        payload: &CreateNoteOptions,
+    ) -> Result<()> {
+        let Some(guard) = self.options.team_visibility_guard else {
+            return Ok(());
+        };
+        // Without `tracing` a warning has nowhere to go, so skip the `get_me` lookup.
+        if cfg!(not(feature = "tracing")) && guard == TeamVisibilityGuard::Warn {
+            return Ok(());
+        }
+        let Some(read_permission @ (NotePermissionRole::SignedIn | NotePermissionRole::Guest)) =
+            &payload.read_permission
+        else {
+            return Ok(());
+        };
+        if self.team_visibility(team_path).await? != Some(TeamVisibilityType::Private) {
+            return Ok(());
+        }
+
+        let message = format!(
+            "read_permission {read_permission:?} makes the note readable outside private team {team_path}"
+        );
+        match guard {
+            TeamVisibilityGuard::Reject => Err(Self::validation_error(message)),
+            TeamVisibilityGuard::Warn => {
+                #[cfg(feature = "tracing")]
+                tracing::warn!(team_path, "{message}");
+                Ok(())
+            }
+        }
+    }
+
+    async fn team_visibility(&self, team_path: &str) -> Result<Option<TeamVisibilityType>> {
+        if let Some(visibility) = self
+            .team_visibility
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .get(team_path)
+        {
+            return Ok(Some(visibility.clone()));
+        }
+
+        let me = self.get_me().await?;
+        let mut cache = self
+            .team_visibility
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        cache.extend(
+            me.teams
+                .into_iter()
+                .map(|team| (team.path, team.visibility)),
+        );
+        Ok(cache.get(team_path).cloned())
+    }
+
+    pub async fn create_team_note_content(
+        &self,
+        team_path: &str,
+        content: &str,
+    ) -> Result<SingleNote> {
+        self.create_team_note_content_with_type(team_path, content, NoteContentType::Json)
+            .await
+    }
+
+    pub async fn create_team_note_content_with_type(
+        &self,
+        team_path: &str,
+        content: &str,
+        content_type: NoteContentType,
+    ) -> Result<SingleNote> {
+        self.check_content(Some(content))?;
+        let idempotency_key = self.generated_idempotency_key("create_team_note");
+        let idempotency_key = idempotency_key.as_deref();
+        self.retry_non_idempotent_request("create_team_note", |base_url| async move {
+            let url = Self::team_notes_url(base_url, team_path)?;
+            let request = self
+                .request(Method::POST, url)
+                .headers(Self::idempotency_headers(idempotency_key)?);
+            let request = self.with_content_body(request, content, content_type);
+            let response = self.execute(request).await?;
+            self.handle_response(response).await
+        })
+        .await
+    }
+
+    pub async fn update_team_note_content(
+        &self,
+        team_path: &str,
+        note_id: &str,
+        content: &str,
+    ) -> Result<()> {
+        let payload = UpdateNoteOptions {
+            content: Some(content.to_string()),
+            ..Default::default()
+        };
+        self.update_team_note(team_path, note_id, &payload).await
+    }
+
+    pub async fn update_team_note(
+        &self,
+        team_path: &str,
+        note_id: &str,
+        payload: &UpdateNoteOptions,
+    ) -> Result<()> {
+        self.check_content(payload.content.as_deref())?;
+        let payload = self.normalized_update_payload(payload);
+        let body = &Self::json_body(&*payload)?;
+        self.retry_request("update_team_note", |base_url| async move {
+            let url = Self::team_note_url(base_url, team_path, note_id)?;
+            let request = Self::with_json_body(self.request(Method::PATCH, url), body);
+            let response = self.execute(request).await?;
+            self.handle_empty_response(response).await
+        })
+        .await
+    }
+
+    pub async fn delete_team_note(&self, team_path: &str, note_id: &str) -> Result<()> {
+        self.retry_request("delete_team_note", |base_url| async move {
+            let url = Self::team_note_url(base_url, team_path, note_id)?;
+            let response = self.execute(self.request(Method::DELETE, url)).await?;
+            self.handle_empty_response(response).await
+        })
+        .await
+    }
+
+    pub async fn get_team_folders(&self, team_path: &str) -> Result<Vec<Folder>> {
+        self.retry_request("get_team_folders", |base_url| async move {
+            let url = Self::team_folders_url(base_url, team_path)?;
+            let response = self.send_get(url).await?;
+            self.handle_response(response).await
+        })
+        .await
+    }
+
+    pub async fn create_team_folder(
+        &self,
+        team_path: &str,
+        payload: &CreateFolderOptions,
+    ) -> Result<Folder> {
+        self.retry_non_idempotent_request("create_team_folder", |base_url| async move {
+            let url = Self::team_folders_url(base_url, team_path)?;
+            let response = self
+                .execute(self.request(Method::POST, url).json(payload))
+                .await?;
+            self.handle_response(response).await
+        })
+        .await
+    }
+
+    pub async fn get_team_folder(&self, team_path: &str, folder_id: &str) -> Result<Folder> {
+        self.retry_request("get_team_folder", |base_url| async move {
+            let url = Self::team_folder_url(base_url, team_path, folder_id)?;
+            let response = self.send_get(url).await?;
+            self.handle_response(response).await
+        })
+        .await
+    }
+
+    pub async fn update_team_folder(
+        &self,
+        team_path: &str,
+        folder_id: &str,
+        payload: &UpdateFolderOptions,
+    ) -> Result<()> {
+        self.retry_request("update_team_folder", |base_url| async move {
+            let url = Self::team_folder_url(base_url, team_path, folder_id)?;
+            let response = self
+                .execute(self.request(Method::PATCH, url).json(payload))
+                .await?;
+            self.handle_empty_response(response).await
+        })
+        .await
+    }
+
+    pub async fn delete_team_folder(&self, team_path: &str, folder_id: &str) -> Result<()> {
+        self.retry_request("delete_team_folder", |base_url| async move {
+            let url = Self::team_folder_url(base_url, team_path, folder_id)?;
+            let response = self.execute(self.request(Method::DELETE, url)).await?;
+            self.handle_empty_response(response).await
+        })
+        .await
+    }
+
+    pub async fn get_team_folder_order(&self, team_path: &str) -> Result<FolderOrder> {
+        self.retry_request("get_team_folder_order", |base_url| async move {
+            let url = Self::team_folder_order_url(base_url, team_path)?;
+            let response = self.send_get(url).await?;
+            self.handle_response(response).await
+        })
+        .await
+    }
+
+    pub async fn update_team_folder_order(
+        &self,
+        team_path: &str,
+        payload: &UpdateFolderOrderOptions,
+    ) -> Result<()> {
+        self.retry_request("update_team_folder_order", |base_url| async move {
+            let url = Self::team_folder_order_url(base_url, team_path)?;
+            let response = self
+                .execute(self.request(Method::PUT, url).json(payload))
+                .await?;
+            self.handle_empty_response(response).await
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::TooManyRequestsError;
+
+    #[test]
+    fn test_api_client_creation() {
+        let client = ApiClient::new("test_token");
+        assert!(client.is_ok());
+    }
+
+    #[test]
+    fn test_api_client_creation_empty_token() {
+        let client = ApiClient::new("   ");
+        assert!(client.is_err());
+
+        if let Err(ApiError::MissingRequiredArgument(err)) = client {
+            assert!(err.message.contains("Missing access token"));
+        } else {
+            panic!("Expected MissingRequiredArgument error");
+        }
+    }
+
+    #[test]
+    fn test_api_client_with_base_url() {
+        let client = ApiClient::with_base_url("test_token", "https://api.example.com/v1")
+            .expect("client should be created");
+
+        assert_eq!(client.base_url.as_str(), "https://api.example.com/v1/");
+    }
+
+    #[test]
+    fn test_api_client_with_options() {
+        let options = ApiClientOptions {
+            wrap_response_errors: false,
+            timeout: Some(time::Duration::from_secs(10)),
+            retry_options: None,
+            ..Default::default()
+        };
+
+        let client = ApiClient::with_options("test_token", None, Some(options));
+        assert!(client.is_ok());
+    }
+
+    #[test]
+    fn test_api_client_with_connect_timeout() {
+        let options = ApiClientOptions {
+            timeout: Some(time::Duration::from_secs(30)),
+            connect_timeout: Some(time::Duration::from_secs(5)),
+            ..Default::default()
+        };
+
+        let client = ApiClient::with_options("test_token", None, Some(options)).unwrap();
+        assert_eq!(
+            client.options.connect_timeout,
+            Some(time::Duration::from_secs(5))
+        );
+    }
+
+    #[test]
+    fn test_http_client_accessor_builds_requests_against_base_url() {
+        let client = ApiClient::with_base_url("test_token", "https://api.example.com/v1").unwrap();
+        let request = client
+            .http_client()
+            .get(client.base_url().join("me").unwrap())
+            .build()
+            .unwrap();
+
+        assert_eq!(request.method(), reqwest::Method::GET);
+        assert_eq!(request.url().as_str(), "https://api.example.com/v1/me");
+    }
+
+    fn assert_invalid_options(options: ApiClientOptions, expected: &str) {
+        match options.validate() {
+            Err(ApiError::Validation(err)) => assert!(
+                err.message.contains(expected),
+                "unexpected message: {}",
+                err.message
+            ),
+            other => panic!("expected validation error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_default_options_are_valid() {
+        assert!(ApiClientOptions::default().validate().is_ok());
+    }
+
+    #[test]
+    fn test_options_validation_rejects_contradictory_settings() {
+        assert_invalid_options(
+            ApiClientOptions {
+                retry_options: Some(RetryOptions {
+                    max_retries: 0,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+            "max_retries",
+        );
+        assert_invalid_options(
+            ApiClientOptions {
+                timeout: Some(time::Duration::from_millis(50)),
+                retry_options: Some(RetryOptions {
+                    base_delay: time::Duration::from_millis(100),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+            "base_delay",
+        );
+        assert_invalid_options(
+            ApiClientOptions {
+                timeout: Some(time::Duration::from_secs(5)),
+                connect_timeout: Some(time::Duration::from_secs(10)),
+                ..Default::default()
+            },
+            "connect_timeout",
+        );
+        assert_invalid_options(
+            ApiClientOptions {
+                timeout: Some(time::Duration::ZERO),
+                ..Default::default()
+            },
+            "greater than zero",
+        );
+        assert_invalid_options(
+            ApiClientOptions {
+                rate_limit: Some(RateLimit {
+                    max_requests: 0,
+                    per: time::Duration::from_secs(1),
+                }),
+                ..Default::default()
+            },
+            "rate_limit",
+        );
+    }
+
+    #[test]
+    fn test_api_client_with_options_validates_options() {
+        let options = ApiClientOptions {
+            retry_options: Some(RetryOptions {
+                max_retries: 0,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let result = ApiClient::with_options("test_token", None, Some(options));
+        assert!(matches!(result, Err(ApiError::Validation(_))));
+    }
+
+    #[test]
+    fn test_with_updated_options_leaves_original_client_unchanged() {
+        let client = ApiClient::with_base_url("test_token", "https://api.example.com/v1").unwrap();
+        let health_check_client = client
+            .with_updated_options(ApiClientOptions {
+                timeout: Some(time::Duration::from_secs(2)),
+                retry_options: None,
+                ..Default::default()
+            })
+            .unwrap();
+
+        let original: &ApiClientOptions = client.as_ref();
+        let updated: &ApiClientOptions = health_check_client.as_ref();
+        assert_eq!(original.timeout, Some(time::Duration::from_secs(30)));
+        assert_eq!(updated.timeout, Some(time::Duration::from_secs(2)));
+        assert!(updated.retry_options.is_none());
+        assert_eq!(health_check_client.base_url(), client.base_url());
+        assert_eq!(
+            health_check_client.default_headers[header::AUTHORIZATION],
+            "Bearer test_token"
+        );
+    }
+
+    #[test]
+    fn test_api_client_accepts_invalid_certs_option() {
+        let options = ApiClientOptions {
+            accept_invalid_certs: true,
+            ..Default::default()
+        };
+
+        let client =
+            ApiClient::with_options("test_token", Some("https://hackmd.internal"), Some(options))
+                .unwrap();
+        assert!(client.options.accept_invalid_certs);
+    }
+
+    #[test]
+    fn test_api_client_with_http2_prior_knowledge() {
+        let options = ApiClientOptions {
+            http2_prior_knowledge: true,
+            ..Default::default()
+        };
+
+        let client = ApiClient::with_options("test_token", None, Some(options)).unwrap();
+        assert!(client.options.http2_prior_knowledge);
+    }
+
+    #[test]
+    fn test_create_note_options_serialization() {
+        let options = CreateNoteOptions {
+            title: Some("Test Note".to_string()),
+            content: Some("# Test Content".to_string()),
+            read_permission: Some(NotePermissionRole::Owner),
+            write_permission: Some(NotePermissionRole::SignedIn),
+            comment_permission: Some(CommentPermissionType::Owners),
+            ..Default::default()
+        };
+
+        let json = serde_json::to_string(&options).unwrap();
+        assert!(json.contains("Test Note"));
+        assert!(json.contains("Test Content"));
+    }
+
+    #[test]
+    fn test_create_note_options_serialization_supports_note_features() {
+        let options = CreateNoteOptions {
+            title: Some("Featureful Note".to_string()),
+            note_features: Some(std::collections::BTreeMap::from([(
+                "comment".to_string(),
+                serde_json::json!({"enabled": true}),
+            )])),
+            ..Default::default()
+        };
+
+        let json = serde_json::to_value(&options).unwrap();
+        assert_eq!(json["title"], "Featureful Note");
+        assert_eq!(json["noteFeatures"]["comment"]["enabled"], true);
+    }
+
+    #[test]
+    fn test_update_note_options_serialization() {
+        let options = UpdateNoteOptions {
+            content: Some("Updated content".to_string()),
+            write_permission: Some(NotePermissionRole::Guest),
+            permalink: Some("custom-permalink".to_string()),
+            ..Default::default()
+        };
+
+        let json = serde_json::to_string(&options).unwrap();
+        assert!(json.contains("Updated content"));
+        assert!(json.contains("guest"));
+        assert!(json.contains("custom-permalink"));
+        assert!(!json.contains("readPermission"));
+    }
+
+    #[test]
+    fn test_note_url_requires_note_id() {
+        let client = ApiClient::new("test_token").unwrap();
+        let error = ApiClient::note_url(&client.base_url, "   ").unwrap_err();
+
+        assert!(matches!(error, ApiError::MissingRequiredArgument(_)));
+    }
+
+    #[test]
+    fn test_folder_url_requires_folder_id() {
+        let client = ApiClient::new("test_token").unwrap();
+        let error = ApiClient::folder_url(&client.base_url, "   ").unwrap_err();
+
+        assert!(matches!(error, ApiError::MissingRequiredArgument(_)));
+    }
+
+    #[test]
+    fn test_team_note_url_requires_team_path() {
+        let client = ApiClient::new("test_token").unwrap();
+        let error = ApiClient::team_note_url(&client.base_url, "", "note-123").unwrap_err();
+
+        assert!(matches!(error, ApiError::MissingRequiredArgument(_)));
+    }
+
+    #[test]
+    fn test_team_folder_url_requires_team_path() {
+        let client = ApiClient::new("test_token").unwrap();
+        let error = ApiClient::team_folder_url(&client.base_url, "", "folder-123").unwrap_err();
+
+        assert!(matches!(error, ApiError::MissingRequiredArgument(_)));
+    }
+
+    #[test]
+    fn test_note_team_and_folder_urls_are_composed_from_valid_identifiers() {
+        let client = ApiClient::new("test_token").unwrap();
+
+        assert_eq!(
+            ApiClient::note_url(&client.base_url, "note-123")
+                .unwrap()
+                .as_str(),
+            "https://api.hackmd.io/v1/notes/note-123"
+        );
+        assert_eq!(
+            ApiClient::note_image_url(&client.base_url, "note-123")
+                .unwrap()
+                .as_str(),
+            "https://api.hackmd.io/v1/notes/note-123/images"
+        );
+        assert_eq!(
+            ApiClient::folders_url(&client.base_url).unwrap().as_str(),
+            "https://api.hackmd.io/v1/folders"
+        );
+        assert_eq!(
+            ApiClient::folder_order_url(&client.base_url)
+                .unwrap()
+                .as_str(),
+            "https://api.hackmd.io/v1/folders/folder-order"
+        );
+        assert_eq!(
+            ApiClient::folder_url(&client.base_url, "folder-123")
+                .unwrap()
+                .as_str(),
+            "https://api.hackmd.io/v1/folders/folder-123"
+        );
+        assert_eq!(
+            ApiClient::team_note_url(&client.base_url, "platform-team", "note-123")
+                .unwrap()
+                .as_str(),
+            "https://api.hackmd.io/v1/teams/platform-team/notes/note-123"
+        );
+        assert_eq!(
+            ApiClient::team_folders_url(&client.base_url, "platform-team")
+                .unwrap()
+                .as_str(),
+            "https://api.hackmd.io/v1/teams/platform-team/folders"
+        );
+        assert_eq!(
+            ApiClient::team_folder_order_url(&client.base_url, "platform-team")
+                .unwrap()
+                .as_str(),
+            "https://api.hackmd.io/v1/teams/platform-team/folders/folder-order"
+        );
+        assert_eq!(
+            ApiClient::team_folder_url(&client.base_url, "platform-team", "folder-123")
+                .unwrap()
+                .as_str(),
+            "https://api.hackmd.io/v1/teams/platform-team/folders/folder-123"
+        );
+    }
+
+    #[test]
+    fn test_resource_urls_percent_encode_path_segments() {
+        let client = ApiClient::new("test_token").unwrap();
+
+        assert_eq!(
+            ApiClient::note_url(&client.base_url, "note/123 ?#")
+                .unwrap()
+                .as_str(),
+            "https://api.hackmd.io/v1/notes/note%2F123%20%3F%23"
+        );
+        assert_eq!(
+            ApiClient::team_note_url(&client.base_url, "platform/team", "note/123")
+                .unwrap()
+                .as_str(),
+            "https://api.hackmd.io/v1/teams/platform%2Fteam/notes/note%2F123"
+        );
+    }
+
+    #[test]
+    fn test_create_folder_options_serialization() {
+        let options = CreateFolderOptions {
+            name: Some("Project Docs".to_string()),
+            parent_folder_id: Some("root-folder".to_string()),
+            description: Some("Shared project docs".to_string()),
+            icon: Some("1F4C1".to_string()),
+            color: Some("#4F46E5".to_string()),
+        };
+
+        let json = serde_json::to_string(&options).unwrap();
+        assert!(json.contains("Project Docs"));
+        assert!(json.contains("root-folder"));
+        assert!(json.contains("Shared project docs"));
+        assert!(json.contains("1F4C1"));
+        assert!(json.contains("#4F46E5"));
+    }
+
+    #[test]
+    fn test_note_deserialization_accepts_double_millisecond_timestamps() {
+        let value = serde_json::json!({
+            "id": "note-123",
+            "title": "Timestamp Note",
+            "description": "",
+            "tags": [],
+            "lastChangedAt": 1_710_000_000_000.0,
+            "createdAt": 1_710_000_000_000.0,
+            "titleUpdatedAt": 1_710_000_000_001.0,
+            "tagsUpdatedAt": null,
+            "lastChangeUser": null,
+            "publishType": "edit",
+            "publishedAt": null,
+            "userPath": "demo-user",
+            "teamPath": null,
+            "permalink": null,
+            "shortId": "short-123",
+            "publishLink": "https://hackmd.io/note-123",
+            "folderPaths": [],
+            "readPermission": "owner",
+            "writePermission": "owner"
+        });
+
+        let note: Note = serde_json::from_value(value).unwrap();
+
+        assert_eq!(note.id, "note-123");
+        assert!(note.title_updated_at.is_some());
+    }
+
+    #[test]
+    fn test_update_folder_options_serialization_supports_null_clears() {
+        let options = UpdateFolderOptions {
+            name: Some("Renamed Folder".to_string()),
+            parent_folder_id: Some(None),
+            description: Some(None),
+            icon: None,
+            color: Some(Some("#16A34A".to_string())),
+        };
+
+        let json = serde_json::to_value(&options).unwrap();
+        assert_eq!(json["name"], "Renamed Folder");
+        assert_eq!(json["parentFolderId"], Value::Null);
+        assert_eq!(json["description"], Value::Null);
+        assert_eq!(json["color"], "#16A34A");
+        assert!(json.get("icon").is_none());
+    }
+
+    #[test]
+    fn test_update_folder_order_options_serialization() {
+        let options = UpdateFolderOrderOptions {
+            order: std::collections::BTreeMap::from([
+                (
+                    "root".to_string(),
+                    vec!["folder-a".to_string(), "folder-b".to_string()],
+                ),
+                (
+                    "folder-a".to_string(),
+                    vec!["folder-c".to_string(), "folder-d".to_string()],
+                ),
+            ]),
+        };
+
+        let json = serde_json::to_value(&options).unwrap();
+        assert_eq!(
+            json["order"]["root"],
+            serde_json::json!(["folder-a", "folder-b"])
+        );
+        assert_eq!(
+            json["order"]["folder-a"],
+            serde_json::json!(["folder-c", "folder-d"])
+        );
+    }
+
+    #[test]
+    fn test_rate_limit_errors_are_retryable() {
+        let error = ApiError::TooManyRequests(TooManyRequestsError {
+            message: "Too many requests".to_string(),
+            code: 429,
+            status_text: "Too Many Requests".to_string(),
+            user_limit: 60,
+            user_remaining: 0,
+            reset_after: Some(1),
+        });
+
+        assert!(ApiClient::is_retryable_error(
+            Some(&RetryOptions::default()),
+            &error
+        ));
+    }
+
+    fn rate_limit_error(reset_after: Option<u64>) -> TooManyRequestsError {
+        TooManyRequestsError {
+            message: "Too many requests".to_string(),
+            code: 429,
+            status_text: "Too Many Requests".to_string(),
+            user_limit: 60,
+            user_remaining: 0,
+            reset_after,
+        }
+    }
+
+    #[test]
+    fn test_rate_limit_retry_after_counts_down_to_future_reset() {
+        let now = time::SystemTime::now()
+            .duration_since(time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let retry_after = rate_limit_error(Some(now + 60)).retry_after().unwrap();
+
+        assert!(retry_after <= time::Duration::from_secs(60));
+        assert!(retry_after > time::Duration::from_secs(58));
+    }
+
+    #[test]
+    fn test_rate_limit_retry_after_clamps_past_reset_to_zero() {
+        assert_eq!(
+            rate_limit_error(Some(1)).retry_after(),
+            Some(time::Duration::ZERO)
+        );
+        assert_eq!(rate_limit_error(None).retry_after(), None);
+    }
+
+    #[test]
+    fn test_retry_delay_waits_for_rate_limit_reset_when_known() {
+        let retry_options = RetryOptions::default();
+        let base_delay = retry_options.base_delay;
+        let now = time::SystemTime::now()
+            .duration_since(time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+
+        let without_headers = ApiError::TooManyRequests(rate_limit_error(None));
+        assert!(ApiClient::is_retryable_error(
+            Some(&retry_options),
+            &without_headers
+        ));
+        assert_eq!(
+            ApiClient::retry_delay(&retry_options, 0, &without_headers, None),
+            Some(base_delay)
+        );
+
+        let past_reset = ApiError::TooManyRequests(rate_limit_error(Some(1)));
+        assert_eq!(
+            ApiClient::retry_delay(&retry_options, 1, &past_reset, None),
+            Some(base_delay * 2)
+        );
+
+        let future_reset = ApiError::TooManyRequests(rate_limit_error(Some(now + 10)));
+        assert!(
+            ApiClient::retry_delay(&retry_options, 0, &future_reset, None).unwrap()
+                > time::Duration::from_secs(8)
+        );
+    }
+
+    #[test]
+    fn test_retry_delay_gives_up_on_far_future_rate_limit_reset() {
+        let retry_options = RetryOptions::default();
+        let now = time::SystemTime::now()
+            .duration_since(time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+
+        let far_reset = ApiError::TooManyRequests(rate_limit_error(Some(now + 3600)));
+        assert_eq!(
+            ApiClient::retry_delay(&retry_options, 0, &far_reset, None),
+            None
+        );
+
+        // A reset header sent in milliseconds lands thousands of years out.
+        let millis_reset = ApiError::TooManyRequests(rate_limit_error(Some(now * 1000)));
+        assert_eq!(
+            ApiClient::retry_delay(&retry_options, 0, &millis_reset, None),
+            None
+        );
+
+        let near_reset = ApiError::TooManyRequests(rate_limit_error(Some(now + 10)));
+        assert_eq!(
+            ApiClient::retry_delay(
+                &retry_options,
+                0,
+                &near_reset,
+                Some(time::Duration::from_secs(2))
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_success_status_accepts_all_2xx_codes() {
+        assert!(ApiClient::is_success_status(StatusCode::OK));
+        assert!(ApiClient::is_success_status(StatusCode::ACCEPTED));
+        assert!(ApiClient::is_success_status(StatusCode::NO_CONTENT));
+        assert!(!ApiClient::is_success_status(StatusCode::BAD_REQUEST));
+    }
+
+    #[test]
+    fn test_exponential_backoff_doubles_between_attempts() {
+        let retry_options = RetryOptions::default();
+
+        assert_eq!(
+            ApiClient::exponential_backoff(&retry_options, 0),
+            time::Duration::from_millis(100)
+        );
+        assert_eq!(
+            ApiClient::exponential_backoff(&retry_options, 1),
+            time::Duration::from_millis(200)
+        );
+        assert_eq!(
+            ApiClient::exponential_backoff(&retry_options, 2),
+            time::Duration::from_millis(400)
+        );
+    }
+
+    #[test]
+    fn test_exponential_backoff_uses_configured_multiplier() {
+        let gentle = RetryOptions {
+            multiplier: 1.5,
+            ..Default::default()
+        };
+        let delays: Vec<u128> = (0..4)
+            .map(|retries| ApiClient::exponential_backoff(&gentle, retries).as_millis())
+            .collect();
+        assert_eq!(delays, [100, 150, 225, 338]);
+
+        let steep = RetryOptions {
+            multiplier: 3.0,
+            ..Default::default()
+        };
+        let delays: Vec<u128> = (0..4)
+            .map(|retries| ApiClient::exponential_backoff(&steep, retries).as_millis())
+            .collect();
+        assert_eq!(delays, [100, 300, 900, 2700]);
+    }
+
+    #[test]
+    fn test_options_validation_rejects_non_positive_multiplier() {
+        for multiplier in [0.0, -2.0, f64::NAN, f64::INFINITY] {
+            assert_invalid_options(
+                ApiClientOptions {
+                    retry_options: Some(RetryOptions {
+                        multiplier,
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                },
+                "multiplier",
+            );
+        }
+    }
+
+    #[test]
+    fn test_exponential_backoff_is_clamped_to_max_delay() {
+        let retry_options = RetryOptions::default();
+
+        assert_eq!(
+            ApiClient::exponential_backoff(&retry_options, 9),
+            time::Duration::from_millis(51_200)
+        );
+        assert_eq!(
+            ApiClient::exponential_backoff(&retry_options, 10),
+            time::Duration::from_secs(60)
+        );
+        assert_eq!(
+            ApiClient::exponential_backoff(&retry_options, 64),
+            time::Duration::from_secs(60)
+        );
+        assert_eq!(
+            ApiClient::exponential_backoff(&retry_options, u32::MAX),
+            time::Duration::from_secs(60)
+        );
+        let steep = RetryOptions {
+            multiplier: f64::MAX,
+            ..Default::default()
+        };
+        assert_eq!(
+            ApiClient::exponential_backoff(&steep, 2),
+            time::Duration::from_secs(60)
+        );
+    }
+
+    fn sample_team(path: &str) -> Team {
+        serde_json::from_value(serde_json::json!({
+            "id": format!("team-{path}"),
+            "ownerId": "owner-123",
+            "name": format!("Team {path}"),
+            "logo": "https://hackmd.io/logo.png",
+            "path": path,
+            "description": null,
+            "visibility": "private",
+            "createdAt": 1_710_000_000_000i64,
+            "upgraded": false
+        }))
+        .unwrap()
+    }
+
+    fn sample_user(team_paths: &[&str]) -> User {
+        User {
+            id: "user-123".to_string(),
+            email: None,
+            name: "Demo User".to_string(),
+            user_path: "demo-user".to_string(),
+            photo: "https://hackmd.io/photo.png".to_string(),
+            teams: team_paths.iter().map(|path| sample_team(path)).collect(),
+            upgraded: false,
+        }
+    }
+
+    #[test]
+    fn test_user_team_map_is_keyed_by_path() {
+        let user = sample_user(&["platform-team", "design-team"]);
+        let teams = user.team_map();
+
+        assert_eq!(teams.len(), 2);
+        assert_eq!(teams["platform-team"].id, "team-platform-team");
+        assert_eq!(teams["design-team"].id, "team-design-team");
     }
 
-    pub async fn get_folder(&self, folder_id: &str) -> Result<Folder> {
-        self.retry_request(|| async {
-            let url = self.folder_url(folder_id)?;
-            let response = self.http_client.get(url).send().await?;
-            self.handle_response(response).await
-        })
-        .await
+    #[test]
+    fn test_user_team_by_path() {
+        let user = sample_user(&["platform-team", "design-team"]);
+
+        assert_eq!(
+            user.team_by_path("design-team")
+                .map(|team| team.id.as_str()),
+            Some("team-design-team")
+        );
+        assert!(user.team_by_path("missing-team").is_none());
     }
 
-    pub async fn update_folder(
-        &self,
-        folder_id: &str,
-        payload: &UpdateFolderOptions,
-    ) -> Result<()> {
-        self.retry_request(|| async {
-            let url = self.folder_url(folder_id)?;
-            let response = self.http_client.patch(url).json(payload).send().await?;
-            self.handle_empty_response(response).await
-        })
-        .await
+    fn sample_single_note() -> SingleNote {
+        serde_json::from_value(serde_json::json!({
+            "id": "note-123",
+            "title": "Sample Note",
+            "description": "",
+            "tags": ["rust"],
+            "lastChangedAt": 1_710_000_000_000i64,
+            "createdAt": 1_710_000_000_000i64,
+            "titleUpdatedAt": null,
+            "tagsUpdatedAt": null,
+            "lastChangeUser": null,
+            "publishType": "edit",
+            "publishedAt": null,
+            "userPath": "demo-user",
+            "teamPath": null,
+            "permalink": null,
+            "shortId": "short-123",
+            "publishLink": "https://hackmd.io/note-123",
+            "folderPaths": [],
+            "readPermission": "owner",
+            "writePermission": "owner",
+            "content": "# Sample"
+        }))
+        .unwrap()
     }
 
-    pub async fn delete_folder(&self, folder_id: &str) -> Result<()> {
-        self.retry_request(|| async {
-            let url = self.folder_url(folder_id)?;
-            let response = self.http_client.delete(url).send().await?;
-            self.handle_empty_response(response).await
-        })
-        .await
+    #[test]
+    fn test_note_fixture_applies_overrides() {
+        let published_at = chrono::DateTime::from_timestamp_millis(1_720_000_000_000).unwrap();
+        let note = testkit::NoteFixture::new()
+            .id("note-456")
+            .title("Fixture Note")
+            .tags(&["rust", "testing"])
+            .content("# Fixture")
+            .team_path("platform-team")
+            .last_change_user("demo-user")
+            .publish_type(NotePublishType::Slide)
+            .published_at(published_at)
+            .permissions(NotePermissionRole::Guest, NotePermissionRole::SignedIn)
+            .build_single_note();
+
+        assert_eq!(note.content, "# Fixture");
+        assert_eq!(note.note.id, "note-456");
+        assert_eq!(note.note.publish_link, "https://hackmd.io/note-456");
+        assert_eq!(note.note.title, "Fixture Note");
+        assert_eq!(note.note.tags, ["rust", "testing"]);
+        assert_eq!(note.note.team_path.as_deref(), Some("platform-team"));
+        assert!(note.note.user_path.is_none());
+        assert!(note.note.is_last_changed_by("demo-user"));
+        assert!(note.note.is_slide() && note.note.is_published());
+        assert_eq!(note.note.read_permission, NotePermissionRole::Guest);
     }
 
-    pub async fn get_folder_order(&self) -> Result<FolderOrder> {
-        self.retry_request(|| async {
-            let url = self.folder_order_url()?;
-            let response = self.http_client.get(url).send().await?;
-            self.handle_response(response).await
-        })
-        .await
+    #[test]
+    fn test_note_fixture_defaults_round_trip_through_serde() {
+        let note = testkit::NoteFixture::new().build_note();
+        let json = serde_json::to_value(&note).unwrap();
+
+        assert_eq!(serde_json::from_value::<Note>(json).unwrap(), note);
     }
 
-    pub async fn update_folder_order(&self, payload: &UpdateFolderOrderOptions) -> Result<()> {
-        self.retry_request(|| async {
-            let url = self.folder_order_url()?;
-            let response = self.http_client.put(url).json(payload).send().await?;
-            self.handle_empty_response(response).await
-        })
-        .await
+    #[test]
+    fn test_diff_notes_reports_no_changes_for_identical_notes() {
+        let note = sample_single_note();
+        let diff = diff_notes(&note, &note.clone());
+
+        assert_eq!(diff, NoteDiff::default());
+        assert!(!diff.has_changes());
     }
 
-    pub async fn get_teams(&self) -> Result<Vec<Team>> {
-        self.retry_request(|| async {
-            let url = self.base_url.join("teams")?;
-            let response = self.http_client.get(url).send().await?;
-            self.handle_response(response).await
-        })
-        .await
+    #[test]
+    fn test_diff_notes_detects_each_changed_field() {
+        let original = sample_single_note();
+
+        let mut changed = original.clone();
+        changed.content = "# Changed".to_string();
+        let diff = diff_notes(&original, &changed);
+        assert!(diff.content && !diff.title && !diff.tags && !diff.permissions);
+
+        let mut changed = original.clone();
+        changed.note.title = "Renamed".to_string();
+        let diff = diff_notes(&original, &changed);
+        assert!(diff.title && !diff.content && !diff.tags && !diff.permissions);
+
+        let mut changed = original.clone();
+        changed.note.tags.push("api".to_string());
+        let diff = diff_notes(&original, &changed);
+        assert!(diff.tags && !diff.content && !diff.title && !diff.permissions);
+
+        let mut changed = original.clone();
+        changed.note.write_permission = NotePermissionRole::SignedIn;
+        let diff = diff_notes(&original, &changed);
+        assert!(diff.permissions && !diff.content && !diff.title && !diff.tags);
+        assert!(diff.has_changes());
     }
 
-    pub async fn get_team_notes(&self, team_path: &str) -> Result<Vec<Note>> {
-        self.retry_request(|| async {
-            let url = self.team_notes_url(team_path)?;
-            let response = self.http_client.get(url).send().await?;
-            self.handle_response(response).await
-        })
-        .await
+    #[test]
+    fn test_note_list_chunks_into_pages_with_final_partial_page() {
+        let notes: Vec<Note> = (0..5)
+            .map(|index| {
+                let mut note = sample_single_note().note;
+                note.id = format!("note-{index}");
+                note
+            })
+            .collect();
+        let mut list = NoteList::new(notes, 2);
+
+        assert_eq!(list.total(), 5);
+        let page_ids = |page: &[Note]| page.iter().map(|n| n.id.clone()).collect::<Vec<_>>();
+        assert_eq!(page_ids(list.next_page().unwrap()), ["note-0", "note-1"]);
+        assert_eq!(page_ids(list.next_page().unwrap()), ["note-2", "note-3"]);
+        assert_eq!(page_ids(list.next_page().unwrap()), ["note-4"]);
+        assert!(!list.has_next_page());
+        assert!(list.next_page().is_none());
     }
 
-    pub async fn get_team_note(&self, team_path: &str, note_id: &str) -> Result<SingleNote> {
-        self.retry_request(|| async {
-            let url = self.team_note_url(team_path, note_id)?;
-            let response = self.http_client.get(url).send().await?;
-            self.handle_response(response).await
-        })
-        .await
+    #[test]
+    fn test_note_list_iterator_yields_every_page() {
+        let notes = vec![sample_single_note().note; 3];
+        let pages: Vec<usize> = NoteList::new(notes, 2).map(|page| page.len()).collect();
+
+        assert_eq!(pages, [2, 1]);
     }
 
-    pub async fn create_team_note(
-        &self,
-        team_path: &str,
-        payload: &CreateNoteOptions,
-    ) -> Result<SingleNote> {
-        self.retry_request(|| async {
-            let url = self.team_notes_url(team_path)?;
-            let response = self.http_client.post(url).json(payload).send().await?;
-            self.handle_response(response).await
-        })
-        .await
+    #[test]
+    fn test_read_types_round_trip_through_serialization() {
+        let note = sample_single_note();
+        let json = serde_json::to_value(&note).unwrap();
+
+        assert_eq!(json["lastChangedAt"], 1_710_000_000_000i64);
+        assert_eq!(json["titleUpdatedAt"], Value::Null);
+        assert_eq!(json["content"], "# Sample");
+        assert_eq!(serde_json::from_value::<SingleNote>(json).unwrap(), note);
+
+        let user = sample_user(&["platform-team"]);
+        let json = serde_json::to_value(&user).unwrap();
+
+        assert_eq!(json["teams"][0]["createdAt"], 1_710_000_000_000i64);
+        assert_eq!(serde_json::from_value::<User>(json).unwrap(), user);
     }
 
-    pub async fn create_team_note_content(
-        &self,
-        team_path: &str,
-        content: &str,
-    ) -> Result<SingleNote> {
-        self.retry_request(|| async {
-            let url = self.team_notes_url(team_path)?;
-            let response = self.http_client.post(url).json(&content).send().await?;
-            self.handle_response(response).await
-        })
-        .await
+    #[test]
+    fn test_note_is_last_changed_by_matches_editor_user_path() {
+        let mut note = sample_single_note().note;
+        assert!(!note.is_last_changed_by("demo-user"));
+
+        note.last_change_user = Some(SimpleUserProfile {
+            name: "Demo User".to_string(),
+            user_path: "demo-user".to_string(),
+            photo: "https://hackmd.io/photo.png".to_string(),
+            biography: None,
+        });
+        assert!(note.is_last_changed_by("demo-user"));
+        assert!(!note.is_last_changed_by("other-user"));
     }
 
-    pub async fn update_team_note_content(
-        &self,
-        team_path: &str,
-        note_id: &str,
-        content: &str,
-    ) -> Result<()> {
-        let payload = UpdateNoteOptions {
-            content: Some(content.to_string()),
+    #[test]
+    fn test_note_options_canonical_json_sorts_keys() {
+        let options = CreateNoteOptions {
+            title: Some("Canonical".to_string()),
+            content: Some("# Canonical".to_string()),
+            tags: Some(vec!["b".to_string(), "a".to_string()]),
+            note_features: Some(std::collections::BTreeMap::from([(
+                "zeta".to_string(),
+                serde_json::json!({"z": 1, "a": 2}),
+            )])),
             ..Default::default()
         };
-        self.update_team_note(team_path, note_id, &payload).await
+
+        assert_eq!(
+            options.to_canonical_json().unwrap(),
+            r##"{"content":"# Canonical","noteFeatures":{"zeta":{"a":2,"z":1}},"tags":["b","a"],"title":"Canonical"}"##
+        );
+
+        let options = UpdateNoteOptions {
+            title: Some("Canonical".to_string()),
+            content: Some("# Canonical".to_string()),
+            read_permission: Some(NotePermissionRole::Guest),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            options.to_canonical_json().unwrap(),
+            r##"{"content":"# Canonical","readPermission":"guest","title":"Canonical"}"##
+        );
     }
 
-    pub async fn update_team_note(
-        &self,
-        team_path: &str,
-        note_id: &str,
-        payload: &UpdateNoteOptions,
-    ) -> Result<()> {
-        self.retry_request(|| async {
-            let url = self.team_note_url(team_path, note_id)?;
-            let response = self.http_client.patch(url).json(payload).send().await?;
-            self.handle_empty_response(response).await
-        })
-        .await
+    #[test]
+    fn test_merge_recent_notes_deduplicates_and_limits_by_last_change() {
+        let note_at = |id: &str, millis: i64| {
+            let mut note = sample_single_note().note;
+            note.id = id.to_string();
+            note.last_changed_at = chrono::DateTime::from_timestamp_millis(millis).unwrap();
+            note
+        };
+        let history = vec![note_at("shared", 3_000), note_at("viewed", 1_000)];
+        let owned = vec![
+            note_at("shared", 3_000),
+            note_at("owned-new", 4_000),
+            note_at("owned-old", 500),
+        ];
+
+        let recent = ApiClient::merge_recent_notes(history, owned, 3);
+        let ids: Vec<&str> = recent.iter().map(|note| note.id.as_str()).collect();
+
+        assert_eq!(ids, ["owned-new", "shared", "viewed"]);
     }
 
-    pub async fn delete_team_note(&self, team_path: &str, note_id: &str) -> Result<()> {
-        self.retry_request(|| async {
-            let url = self.team_note_url(team_path, note_id)?;
-            let response = self.http_client.delete(url).send().await?;
-            self.handle_empty_response(response).await
-        })
-        .await
+    #[test]
+    fn test_note_publish_state_predicates() {
+        let mut note = sample_single_note().note;
+        assert!(!note.is_published());
+        assert!(!note.is_slide());
+        assert!(!note.is_book());
+
+        note.published_at = Some(note.created_at);
+        assert!(note.is_published());
+
+        for (publish_type, slide, book) in [
+            (NotePublishType::Edit, false, false),
+            (NotePublishType::View, false, false),
+            (NotePublishType::Slide, true, false),
+            (NotePublishType::Book, false, true),
+        ] {
+            note.publish_type = publish_type;
+            assert_eq!(note.is_slide(), slide);
+            assert_eq!(note.is_book(), book);
+        }
     }
 
-    pub async fn get_team_folders(&self, team_path: &str) -> Result<Vec<Folder>> {
-        self.retry_request(|| async {
-            let url = self.team_folders_url(team_path)?;
-            let response = self.http_client.get(url).send().await?;
-            self.handle_response(response).await
-        })
-        .await
+    #[test]
+    fn test_body_without_frontmatter_strips_leading_block() {
+        let mut note = sample_single_note();
+        note.content = "---\ntitle: Frontmatter\n---\n# Body\n".to_string();
+        assert_eq!(note.body_without_frontmatter(), "# Body\n");
+
+        note.content = "---\r\ntitle: Frontmatter\r\n---\r\n# Body".to_string();
+        assert_eq!(note.body_without_frontmatter(), "# Body");
     }
 
-    pub async fn create_team_folder(
-        &self,
-        team_path: &str,
-        payload: &CreateFolderOptions,
-    ) -> Result<Folder> {
-        self.retry_request(|| async {
-            let url = self.team_folders_url(team_path)?;
-            let response = self.http_client.post(url).json(payload).send().await?;
-            self.handle_response(response).await
-        })
-        .await
+    #[test]
+    fn test_body_without_frontmatter_keeps_content_without_leading_block() {
+        let mut note = sample_single_note();
+        assert_eq!(note.body_without_frontmatter(), "# Sample");
+
+        note.content = "# Title\n\n---\nnot: frontmatter\n---\n".to_string();
+        assert_eq!(note.body_without_frontmatter(), note.content);
+
+        note.content = "---\nhorizontal rule without closing delimiter".to_string();
+        assert_eq!(note.body_without_frontmatter(), note.content);
+
+        note.content = "----\nnot: frontmatter\n---\n".to_string();
+        assert_eq!(note.body_without_frontmatter(), note.content);
     }
 
-    pub async fn get_team_folder(&self, team_path: &str, folder_id: &str) -> Result<Folder> {
-        self.retry_request(|| async {
-            let url = self.team_folder_url(team_path, folder_id)?;
-            let response = self.http_client.get(url).send().await?;
-            self.handle_response(response).await
-        })
-        .await
+    #[cfg(feature = "frontmatter")]
+    #[test]
+    fn test_single_note_frontmatter_parses_leading_yaml_block() {
+        let mut note = sample_single_note();
+        note.content = "---\ntitle: Frontmatter\ntags: [rust, api]\n---\n# Body".to_string();
+
+        let frontmatter = note.frontmatter().unwrap();
+        assert_eq!(frontmatter["title"].as_str(), Some("Frontmatter"));
+        assert_eq!(frontmatter["tags"][1].as_str(), Some("api"));
     }
 
-    pub async fn update_team_folder(
-        &self,
-        team_path: &str,
-        folder_id: &str,
-        payload: &UpdateFolderOptions,
-    ) -> Result<()> {
-        self.retry_request(|| async {
-            let url = self.team_folder_url(team_path, folder_id)?;
-            let response = self.http_client.patch(url).json(payload).send().await?;
-            self.handle_empty_response(response).await
-        })
-        .await
+    #[cfg(feature = "frontmatter")]
+    #[test]
+    fn test_single_note_frontmatter_is_none_when_absent_or_malformed() {
+        let mut note = sample_single_note();
+        assert!(note.frontmatter().is_none());
+
+        note.content = "---\ntitle: [unclosed\n---\n# Body".to_string();
+        assert!(note.frontmatter().is_none());
+
+        note.content = "---\ntitle: Unterminated\n# Body".to_string();
+        assert!(note.frontmatter().is_none());
     }
 
-    pub async fn delete_team_folder(&self, team_path: &str, folder_id: &str) -> Result<()> {
-        self.retry_request(|| async {
-            let url = self.team_folder_url(team_path, folder_id)?;
-            let response = self.http_client.delete(url).send().await?;
-            self.handle_empty_response(response).await
-        })
-        .await
+    #[test]
+    fn test_note_permissions_builder_accepts_valid_combinations() {
+        let permissions = NotePermissions::builder()
+            .read(NotePermissionRole::Guest)
+            .write(NotePermissionRole::SignedIn)
+            .comment(CommentPermissionType::Everyone)
+            .build()
+            .unwrap();
+
+        let mut options = CreateNoteOptions::default();
+        permissions.apply_to(&mut options);
+        assert_eq!(options.read_permission, Some(NotePermissionRole::Guest));
+        assert_eq!(options.write_permission, Some(NotePermissionRole::SignedIn));
+        assert_eq!(
+            options.comment_permission,
+            Some(CommentPermissionType::Everyone)
+        );
+
+        assert!(NotePermissions::builder()
+            .read(NotePermissionRole::Owner)
+            .write(NotePermissionRole::Owner)
+            .comment(CommentPermissionType::Owners)
+            .build()
+            .is_ok());
+        assert!(NotePermissions::builder()
+            .write(NotePermissionRole::Guest)
+            .build()
+            .is_ok());
     }
 
-    pub async fn get_team_folder_order(&self, team_path: &str) -> Result<FolderOrder> {
-        self.retry_request(|| async {
-            let url = self.team_folder_order_url(team_path)?;
-            let response = self.http_client.get(url).send().await?;
-            self.handle_response(response).await
-        })
-        .await
+    #[test]
+    fn test_note_permissions_builder_rejects_invalid_combinations() {
+        let write_more_open_than_read = NotePermissions::builder()
+            .read(NotePermissionRole::SignedIn)
+            .write(NotePermissionRole::Guest)
+            .build();
+        assert!(matches!(
+            write_more_open_than_read,
+            Err(ApiError::Validation(ref err)) if err.message.contains("write permission")
+        ));
+
+        let comment_more_open_than_read = NotePermissions::builder()
+            .read(NotePermissionRole::Owner)
+            .comment(CommentPermissionType::SignedInUsers)
+            .build();
+        assert!(matches!(
+            comment_more_open_than_read,
+            Err(ApiError::Validation(ref err)) if err.message.contains("comment permission")
+        ));
     }
 
-    pub async fn update_team_folder_order(
-        &self,
-        team_path: &str,
-        payload: &UpdateFolderOrderOptions,
-    ) -> Result<()> {
-        self.retry_request(|| async {
-            let url = self.team_folder_order_url(team_path)?;
-            let response = self.http_client.put(url).json(payload).send().await?;
-            self.handle_empty_response(response).await
-        })
-        .await
+    #[test]
+    fn test_note_content_eq_ignores_timestamps() {
+        let note = sample_single_note().note;
+        let later = chrono::DateTime::from_timestamp_millis(1_720_000_000_000).unwrap();
+
+        let mut touched = note.clone();
+        touched.last_changed_at = later;
+        touched.created_at = later;
+        touched.title_updated_at = Some(later);
+        touched.tags_updated_at = Some(later);
+        assert_ne!(note, touched);
+        assert!(note.content_eq(&touched));
+
+        let mut renamed = note.clone();
+        renamed.title = "Renamed".to_string();
+        assert!(!note.content_eq(&renamed));
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[test]
+    fn test_api_error_converts_to_io_error_kind() {
+        let timeout = std::io::Error::from(ApiError::Timeout(TimeoutError {
+            message: "Timed out".to_string(),
+            timeout: time::Duration::from_secs(1),
+        }));
+        assert_eq!(timeout.kind(), std::io::ErrorKind::TimedOut);
+
+        let generic = std::io::Error::from(ApiError::HackMD(HackMDError {
+            message: "Something went wrong".to_string(),
+        }));
+        assert_eq!(generic.kind(), std::io::ErrorKind::Other);
+        assert!(generic.to_string().contains("Something went wrong"));
+    }
 
     #[test]
-    fn test_api_client_creation() {
-        let client = ApiClient::new("test_token");
-        assert!(client.is_ok());
+    fn test_create_note_options_from_template_substitutes_placeholders() {
+        let vars = std::collections::HashMap::from([
+            ("name".to_string(), "Weekly sync".to_string()),
+            ("date".to_string(), "2026-10-15".to_string()),
+        ]);
+
+        let options =
+            CreateNoteOptions::from_template("# {{name}}\n\nDate: {{ date }}", &vars).unwrap();
+        assert_eq!(
+            options.content.as_deref(),
+            Some("# Weekly sync\n\nDate: 2026-10-15")
+        );
     }
 
     #[test]
-    fn test_api_client_creation_empty_token() {
-        let client = ApiClient::new("   ");
-        assert!(client.is_err());
+    fn test_create_note_options_from_template_handles_unresolved_placeholders() {
+        let vars =
+            std::collections::HashMap::from([("name".to_string(), "Weekly sync".to_string())]);
+        let template = "# {{name}} by {{owner}}";
 
-        if let Err(ApiError::MissingRequiredArgument(err)) = client {
-            assert!(err.message.contains("Missing access token"));
-        } else {
-            panic!("Expected MissingRequiredArgument error");
+        let strict = CreateNoteOptions::from_template(template, &vars);
+        assert!(matches!(
+            strict,
+            Err(ApiError::Validation(ref err)) if err.message.contains("{{owner}}")
+        ));
+
+        let lenient = CreateNoteOptions::from_template_lenient(template, &vars);
+        assert_eq!(
+            lenient.content.as_deref(),
+            Some("# Weekly sync by {{owner}}")
+        );
+    }
+
+    #[test]
+    fn test_single_note_flatten_round_trips_realistic_payload() {
+        let payload = serde_json::json!({
+            "id": "ppKJ4fG9SVWAX6ueb3Rqaw",
+            "title": "Release checklist",
+            "description": "Steps for cutting a release",
+            "tags": ["release", "ops"],
+            "createdAt": 1_710_000_000_000i64,
+            "lastChangedAt": 1_710_000_360_000i64,
+            "lastChangeUser": {
+                "name": "Demo User",
+                "userPath": "demo-user",
+                "photo": "https://hackmd.io/photo.png",
+                "biography": null
+            },
+            "publishType": "view",
+            "publishedAt": 1_710_000_100_000i64,
+            "permalink": "release-checklist",
+            "publishLink": "https://hackmd.io/@demo-user/release-checklist",
+            "shortId": "S1abcdEfG",
+            "userPath": "demo-user",
+            "teamPath": null,
+            "folderPaths": [{
+                "id": "folder-1",
+                "name": "Ops",
+                "clientId": "client-1"
+            }],
+            "readPermission": "guest",
+            "writePermission": "signed_in",
+            "content": "# Release checklist\n\n- [ ] Tag"
+        });
+
+        let note: SingleNote = serde_json::from_value(payload.clone()).unwrap();
+        assert_eq!(note.content, "# Release checklist\n\n- [ ] Tag");
+        assert_eq!(note.note.id, "ppKJ4fG9SVWAX6ueb3Rqaw");
+        assert_eq!(note.note.tags, ["release", "ops"]);
+        assert_eq!(note.note.title_updated_at, None);
+        assert_eq!(note.note.tags_updated_at, None);
+        assert!(note.note.is_last_changed_by("demo-user"));
+        assert_eq!(note.note.folder_paths[0].client_id, "client-1");
+        assert_eq!(note.note.read_permission, NotePermissionRole::Guest);
+        assert_eq!(note.note.write_permission, NotePermissionRole::SignedIn);
+
+        let serialized = serde_json::to_value(&note).unwrap();
+        for (key, value) in payload.as_object().unwrap() {
+            assert_eq!(&serialized[key], value, "field {key} did not round-trip");
         }
+        let reparsed: SingleNote = serde_json::from_value(serialized).unwrap();
+        assert_eq!(reparsed, note);
     }
 
     #[test]
-    fn test_api_client_with_base_url() {
-        let client = ApiClient::with_base_url("test_token", "https://api.example.com/v1")
-            .expect("client should be created");
+    fn test_single_note_url_builders_use_publish_link_origin() {
+        let note = sample_single_note();
+        assert_eq!(note.edit_url(), "https://hackmd.io/note-123?edit");
+        assert_eq!(note.view_url(), "https://hackmd.io/s/short-123");
+        assert_eq!(note.slide_url(), "https://hackmd.io/p/short-123");
+        assert_eq!(note.book_url(), "https://hackmd.io/c/short-123");
 
-        assert_eq!(client.base_url.as_str(), "https://api.example.com/v1/");
+        let mut self_hosted = note.clone();
+        self_hosted.note.publish_link = "https://md.example.com:8443/@demo/sample".to_string();
+        assert_eq!(
+            self_hosted.view_url(),
+            "https://md.example.com:8443/s/short-123"
+        );
+
+        let mut unparsable = note;
+        unparsable.note.publish_link = String::new();
+        assert_eq!(unparsable.slide_url(), "https://hackmd.io/p/short-123");
     }
 
     #[test]
-    fn test_api_client_with_options() {
-        let options = ApiClientOptions {
-            wrap_response_errors: false,
-            timeout: Some(time::Duration::from_secs(10)),
-            retry_options: None,
-        };
+    fn test_note_list_body_accepts_array_and_enveloped_shapes() {
+        let note = serde_json::to_value(sample_single_note().note).unwrap();
 
-        let client = ApiClient::with_options("test_token", None, Some(options));
-        assert!(client.is_ok());
+        for body in [
+            serde_json::json!([note.clone()]),
+            serde_json::json!({ "notes": [note.clone()] }),
+            serde_json::json!({ "data": [note.clone()] }),
+        ] {
+            let NoteListBody(notes) = serde_json::from_value(body).unwrap();
+            assert_eq!(notes.len(), 1);
+            assert_eq!(notes[0].id, "note-123");
+        }
+
+        let error = serde_json::from_value::<NoteListBody>(serde_json::json!({ "items": [] }))
+            .err()
+            .unwrap();
+        assert!(error.to_string().contains("`notes` or `data`"));
+    }
+
+    #[test]
+    fn test_with_options_requires_http_base_url_scheme() {
+        let result = ApiClient::with_base_url("test_token", "file:///etc/hackmd");
+        assert!(matches!(
+            result,
+            Err(ApiError::Validation(ref err)) if err.message.contains("http or https")
+        ));
+
+        let client = ApiClient::with_base_url("test_token", "https://api.example.com/v1").unwrap();
+        assert_eq!(client.base_url().scheme(), "https");
+    }
+
+    fn assert_send<T: Send>(_: &T) {}
+
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn test_client_types_and_futures_are_send() {
+        assert_send_sync::<ApiClient>();
+        assert_send_sync::<TeamClient>();
+        assert_send_sync::<BatchUpdater>();
+        assert_send_sync::<ApiError>();
+
+        let client = ApiClient::with_base_url("test_token", "https://api.example.com/v1").unwrap();
+        let payload = CreateNoteOptions::default();
+        assert_send(&client.get_me());
+        assert_send(&client.get_note_list());
+        assert_send(&client.create_note(&payload));
+        assert_send(&client.update_notes(&["note-123"], &UpdateNoteOptions::default(), 2));
+        assert_send(&client.get_all_team_notes());
     }
 
     #[test]
-    fn test_create_note_options_serialization() {
-        let options = CreateNoteOptions {
-            title: Some("Test Note".to_string()),
-            content: Some("# Test Content".to_string()),
-            read_permission: Some(NotePermissionRole::Owner),
-            write_permission: Some(NotePermissionRole::SignedIn),
-            comment_permission: Some(CommentPermissionType::Owners),
-            ..Default::default()
+    fn test_note_is_writable_by_owner_or_open_write_permission() {
+        let user = sample_user(&["platform-team"]);
+        let note = |user_path: Option<&str>, team_path: Option<&str>, write| {
+            let mut note = sample_single_note().note;
+            note.user_path = user_path.map(str::to_string);
+            note.team_path = team_path.map(str::to_string);
+            note.write_permission = write;
+            note
         };
 
-        let json = serde_json::to_string(&options).unwrap();
-        assert!(json.contains("Test Note"));
-        assert!(json.contains("Test Content"));
+        assert!(note(Some("demo-user"), None, NotePermissionRole::Owner).is_writable_by(&user));
+        assert!(note(None, Some("platform-team"), NotePermissionRole::Owner).is_writable_by(&user));
+        assert!(!note(Some("someone-else"), None, NotePermissionRole::Owner).is_writable_by(&user));
+        assert!(!note(None, Some("other-team"), NotePermissionRole::Owner).is_writable_by(&user));
+        assert!(
+            note(Some("someone-else"), None, NotePermissionRole::SignedIn).is_writable_by(&user)
+        );
+        assert!(note(Some("someone-else"), None, NotePermissionRole::Guest).is_writable_by(&user));
     }
 
     #[test]
-    fn test_create_note_options_serialization_supports_note_features() {
-        let options = CreateNoteOptions {
-            title: Some("Featureful Note".to_string()),
-            note_features: Some(std::collections::BTreeMap::from([(
-                "comment".to_string(),
-                serde_json::json!({"enabled": true}),
-            )])),
-            ..Default::default()
-        };
+    fn test_note_options_deserialize_from_partial_camel_case_json() {
+        let options: CreateNoteOptions = serde_json::from_value(serde_json::json!({
+            "title": "From config",
+            "tags": ["ops"],
+            "readPermission": "signed_in",
+            "commentPermission": "owners",
+            "parentFolderId": "folder-1"
+        }))
+        .unwrap();
 
-        let json = serde_json::to_value(&options).unwrap();
-        assert_eq!(json["title"], "Featureful Note");
-        assert_eq!(json["noteFeatures"]["comment"]["enabled"], true);
+        assert_eq!(
+            options,
+            CreateNoteOptions {
+                title: Some("From config".to_string()),
+                tags: Some(vec!["ops".to_string()]),
+                read_permission: Some(NotePermissionRole::SignedIn),
+                comment_permission: Some(CommentPermissionType::Owners),
+                parent_folder_id: Some("folder-1".to_string()),
+                ..Default::default()
+            }
+        );
+
+        let update: UpdateNoteOptions =
+            serde_json::from_str(r#"{ "writePermission": "owner" }"#).unwrap();
+        assert_eq!(update.write_permission, Some(NotePermissionRole::Owner));
+        assert_eq!(update.content, None);
     }
 
     #[test]
-    fn test_update_note_options_serialization() {
-        let options = UpdateNoteOptions {
-            content: Some("Updated content".to_string()),
-            write_permission: Some(NotePermissionRole::Guest),
-            permalink: Some("custom-permalink".to_string()),
-            ..Default::default()
-        };
+    fn test_map_status_error_maps_status_codes_to_variants() {
+        let mut headers = header::HeaderMap::new();
+        headers.insert("x-ratelimit-userlimit", "100".parse().unwrap());
+        headers.insert("x-ratelimit-userremaining", "0".parse().unwrap());
+        headers.insert("x-ratelimit-userreset", "1710000000".parse().unwrap());
 
-        let json = serde_json::to_string(&options).unwrap();
-        assert!(json.contains("Updated content"));
-        assert!(json.contains("guest"));
-        assert!(json.contains("custom-permalink"));
-        assert!(!json.contains("readPermission"));
+        match map_status_error(StatusCode::TOO_MANY_REQUESTS, &headers, "slow down") {
+            ApiError::TooManyRequests(err) => {
+                assert_eq!(err.code, 429);
+                assert_eq!(err.user_limit, 100);
+                assert_eq!(err.user_remaining, 0);
+                assert_eq!(err.reset_after, Some(1_710_000_000));
+                assert!(err.message.ends_with(": slow down"));
+            }
+            other => panic!("expected TooManyRequests, got {other:?}"),
+        }
+
+        let empty = header::HeaderMap::new();
+        assert!(matches!(
+            map_status_error(StatusCode::FORBIDDEN, &empty, ""),
+            ApiError::Forbidden(ref err) if err.code == 403 && err.status_text == "Forbidden"
+        ));
+        assert!(matches!(
+            map_status_error(StatusCode::CONFLICT, &empty, ""),
+            ApiError::Conflict(ref err) if err.code == 409 && err.suggested_permalink.is_none()
+        ));
+        assert!(matches!(
+            map_status_error(StatusCode::BAD_GATEWAY, &empty, ""),
+            ApiError::InternalServer(ref err) if err.code == 502
+        ));
+        assert!(matches!(
+            map_status_error(StatusCode::NOT_FOUND, &empty, "Note not found"),
+            ApiError::HttpResponse(ref err)
+                if err.code == 404 && err.message.contains("Note not found")
+        ));
     }
 
     #[test]
-    fn test_note_url_requires_note_id() {
-        let client = ApiClient::new("test_token").unwrap();
-        let error = client.note_url("   ").unwrap_err();
+    fn test_partition_notes_splits_personal_and_team_notes() {
+        let note = |id: &str, team_path: Option<&str>| {
+            let mut note = sample_single_note().note;
+            note.id = id.to_string();
+            note.user_path = team_path.is_none().then(|| "demo-user".to_string());
+            note.team_path = team_path.map(str::to_string);
+            note
+        };
+        let ids = |notes: &[Note]| notes.iter().map(|n| n.id.clone()).collect::<Vec<_>>();
 
-        assert!(matches!(error, ApiError::MissingRequiredArgument(_)));
+        let (personal, teams) = partition_notes(vec![note("a", None), note("b", None)]);
+        assert_eq!(ids(&personal), ["a", "b"]);
+        assert!(teams.is_empty());
+
+        let (personal, teams) = partition_notes(vec![note("c", Some("platform-team"))]);
+        assert!(personal.is_empty());
+        assert_eq!(ids(&teams["platform-team"]), ["c"]);
+
+        let (personal, teams) = partition_notes(vec![
+            note("d", Some("platform-team")),
+            note("e", None),
+            note("f", Some("design-team")),
+            note("g", Some("platform-team")),
+        ]);
+        assert_eq!(ids(&personal), ["e"]);
+        assert_eq!(teams.len(), 2);
+        assert_eq!(ids(&teams["platform-team"]), ["d", "g"]);
+        assert_eq!(ids(&teams["design-team"]), ["f"]);
     }
 
     #[test]
-    fn test_folder_url_requires_folder_id() {
-        let client = ApiClient::new("test_token").unwrap();
-        let error = client.folder_url("   ").unwrap_err();
+    fn test_note_ref_deduplicates_by_id() {
+        let note = sample_single_note().note;
+        let mut edited = note.clone();
+        edited.title = "Edited".to_string();
+        edited.last_changed_at =
+            chrono::DateTime::from_timestamp_millis(1_720_000_000_000).unwrap();
+        let mut other = note.clone();
+        other.id = "note-456".to_string();
 
-        assert!(matches!(error, ApiError::MissingRequiredArgument(_)));
+        let notes: HashSet<NoteRef> = [note, edited, other]
+            .into_iter()
+            .map(NoteRef::from)
+            .collect();
+
+        assert_eq!(notes.len(), 2);
+        assert!(notes.iter().any(|note| note.0.id == "note-123"));
+        assert!(notes.iter().any(|note| note.0.id == "note-456"));
     }
 
     #[test]
-    fn test_team_note_url_requires_team_path() {
-        let client = ApiClient::new("test_token").unwrap();
-        let error = client.team_note_url("", "note-123").unwrap_err();
-
-        assert!(matches!(error, ApiError::MissingRequiredArgument(_)));
+    fn test_next_permalink_appends_or_increments_numeric_suffix() {
+        assert_eq!(next_permalink("weekly-sync"), "weekly-sync-2");
+        assert_eq!(next_permalink("weekly-sync-2"), "weekly-sync-3");
+        assert_eq!(next_permalink("notes"), "notes-2");
+        assert_eq!(next_permalink("-9"), "-9-2");
     }
 
     #[test]
-    fn test_team_folder_url_requires_team_path() {
-        let client = ApiClient::new("test_token").unwrap();
-        let error = client.team_folder_url("", "folder-123").unwrap_err();
+    fn test_rewrite_note_links_maps_hackmd_note_urls() {
+        let mapping = std::collections::HashMap::from([
+            ("S1abcdEfG".to_string(), "setup.md".to_string()),
+            ("release-checklist".to_string(), "release.md".to_string()),
+        ]);
+        let content = "\
+See [setup](https://hackmd.io/s/S1abcdEfG#install) and [slides](https://hackmd.io/p/S1abcdEfG).
+Also [release](https://hackmd.io/@demo-user/release-checklist \"Release\").
+Keep [unknown](https://hackmd.io/s/Unmapped), [docs](https://docs.rs/S1abcdEfG) and [local](./a.md).";
 
-        assert!(matches!(error, ApiError::MissingRequiredArgument(_)));
+        assert_eq!(
+            rewrite_note_links(content, &mapping),
+            "\
+See [setup](setup.md#install) and [slides](setup.md).
+Also [release](release.md \"Release\").
+Keep [unknown](https://hackmd.io/s/Unmapped), [docs](https://docs.rs/S1abcdEfG) and [local](./a.md)."
+        );
     }
 
     #[test]
-    fn test_note_team_and_folder_urls_are_composed_from_valid_identifiers() {
-        let client = ApiClient::new("test_token").unwrap();
+    fn test_local_address_option_is_accepted() {
+        let options = ApiClientOptions {
+            local_address: Some(std::net::IpAddr::from([127, 0, 0, 1])),
+            ..Default::default()
+        };
 
+        let client = ApiClient::with_options("test_token", None, Some(options)).unwrap();
+        let options: &ApiClientOptions = client.as_ref();
         assert_eq!(
-            client.note_url("note-123").unwrap().as_str(),
-            "https://api.hackmd.io/v1/notes/note-123"
+            options.local_address,
+            Some(std::net::IpAddr::from([127, 0, 0, 1]))
         );
+    }
+
+    #[test]
+    fn test_photo_and_logo_urls_resolve_against_base() {
+        let base = Url::parse("https://hackmd.io/").unwrap();
+        let mut user = sample_user(&["platform-team"]);
+
         assert_eq!(
-            client.note_image_url("note-123").unwrap().as_str(),
-            "https://api.hackmd.io/v1/notes/note-123/images"
+            user.photo_url(&base).unwrap().as_str(),
+            "https://hackmd.io/photo.png"
         );
+
+        user.photo = "/uploads/avatar.png".to_string();
         assert_eq!(
-            client.folders_url().unwrap().as_str(),
-            "https://api.hackmd.io/v1/folders"
+            user.photo_url(&base).unwrap().as_str(),
+            "https://hackmd.io/uploads/avatar.png"
         );
+
+        user.photo = "http://[not-a-host".to_string();
+        assert_eq!(user.photo_url(&base), None);
+        user.photo = String::new();
+        assert_eq!(user.photo_url(&base), None);
+
+        let mut team = sample_team("platform-team");
+        team.logo = "logos/team.png".to_string();
         assert_eq!(
-            client.folder_order_url().unwrap().as_str(),
-            "https://api.hackmd.io/v1/folders/folder-order"
+            team.logo_url(&base).unwrap().as_str(),
+            "https://hackmd.io/logos/team.png"
         );
+    }
+
+    #[test]
+    fn test_update_options_distinguish_empty_and_missing_content() {
+        let clear = UpdateNoteOptions {
+            content: Some(String::new()),
+            ..Default::default()
+        };
         assert_eq!(
-            client.folder_url("folder-123").unwrap().as_str(),
-            "https://api.hackmd.io/v1/folders/folder-123"
+            serde_json::to_value(&clear).unwrap(),
+            serde_json::json!({ "content": "" })
         );
+
+        let untouched = UpdateNoteOptions::default();
         assert_eq!(
-            client
-                .team_note_url("platform-team", "note-123")
-                .unwrap()
-                .as_str(),
-            "https://api.hackmd.io/v1/teams/platform-team/notes/note-123"
+            serde_json::to_value(&untouched).unwrap(),
+            serde_json::json!({})
         );
+    }
+
+    #[test]
+    fn test_create_note_permission_presets_serialize_coherent_permissions() {
+        assert_eq!(NotePermissionRole::default(), NotePermissionRole::Owner);
         assert_eq!(
-            client.team_folders_url("platform-team").unwrap().as_str(),
-            "https://api.hackmd.io/v1/teams/platform-team/folders"
+            CommentPermissionType::default(),
+            CommentPermissionType::Owners
         );
+
         assert_eq!(
-            client
-                .team_folder_order_url("platform-team")
-                .unwrap()
-                .as_str(),
-            "https://api.hackmd.io/v1/teams/platform-team/folders/folder-order"
+            serde_json::to_value(CreateNoteOptions::private()).unwrap(),
+            serde_json::json!({
+                "readPermission": "owner",
+                "writePermission": "owner",
+                "commentPermission": "owners"
+            })
         );
         assert_eq!(
-            client
-                .team_folder_url("platform-team", "folder-123")
-                .unwrap()
-                .as_str(),
-            "https://api.hackmd.io/v1/teams/platform-team/folders/folder-123"
+            serde_json::to_value(CreateNoteOptions::public()).unwrap(),
+            serde_json::json!({
+                "readPermission": "guest",
+                "writePermission": "owner",
+                "commentPermission": "signed_in_users"
+            })
         );
     }
 
+    #[cfg(feature = "latency")]
     #[test]
-    fn test_resource_urls_percent_encode_path_segments() {
-        let client = ApiClient::new("test_token").unwrap();
+    fn test_latency_recorder_reports_percentiles_per_endpoint() {
+        let recorder = crate::latency::LatencyRecorder::default();
+        for millis in 1..=100 {
+            recorder.record("GET notes".to_string(), time::Duration::from_millis(millis));
+        }
+        recorder.record("GET me".to_string(), time::Duration::from_millis(5));
+
+        let snapshot = recorder.snapshot();
+        let notes = snapshot["GET notes"];
+        let within = |actual: time::Duration, expected_ms: u64| {
+            actual.abs_diff(time::Duration::from_millis(expected_ms))
+                < time::Duration::from_millis(1)
+        };
+        assert_eq!(notes.count, 100);
+        assert!(within(notes.p50, 50), "p50 was {:?}", notes.p50);
+        assert!(within(notes.p95, 95), "p95 was {:?}", notes.p95);
+        assert!(within(notes.p99, 99), "p99 was {:?}", notes.p99);
+        assert!(within(notes.max, 100), "max was {:?}", notes.max);
+        assert_eq!(snapshot["GET me"].count, 1);
+    }
 
+    #[cfg(feature = "latency")]
+    #[test]
+    fn test_endpoint_key_replaces_identifier_segments() {
+        let client = ApiClient::with_base_url("test_token", "https://api.example.com/v1").unwrap();
+        let url = |path: &str| Url::parse(&format!("https://api.example.com/v1/{path}")).unwrap();
+
+        assert_eq!(client.endpoint_key(&Method::GET, &url("me")), "GET me");
         assert_eq!(
-            client.note_url("note/123 ?#").unwrap().as_str(),
-            "https://api.hackmd.io/v1/notes/note%2F123%20%3F%23"
+            client.endpoint_key(&Method::PATCH, &url("notes/note-123")),
+            "PATCH notes/:id"
         );
         assert_eq!(
-            client
-                .team_note_url("platform/team", "note/123")
-                .unwrap()
-                .as_str(),
-            "https://api.hackmd.io/v1/teams/platform%2Fteam/notes/note%2F123"
+            client.endpoint_key(&Method::GET, &url("teams/platform/notes/note-123")),
+            "GET teams/:id/notes/:id"
+        );
+        assert_eq!(
+            client.endpoint_key(&Method::PUT, &url("teams/platform/folders/folder-order")),
+            "PUT teams/:id/folders/folder-order"
         );
     }
 
     #[test]
-    fn test_create_folder_options_serialization() {
-        let options = CreateFolderOptions {
-            name: Some("Project Docs".to_string()),
-            parent_folder_id: Some("root-folder".to_string()),
-            description: Some("Shared project docs".to_string()),
-            icon: Some("1F4C1".to_string()),
-            color: Some("#4F46E5".to_string()),
-        };
+    fn test_reject_binary_content_allows_markdown_and_rejects_null_bytes() {
+        let client = ApiClient::with_options(
+            "test_token",
+            None,
+            Some(ApiClientOptions {
+                reject_binary_content: true,
+                ..Default::default()
+            }),
+        )
+        .unwrap();
 
-        let json = serde_json::to_string(&options).unwrap();
-        assert!(json.contains("Project Docs"));
-        assert!(json.contains("root-folder"));
-        assert!(json.contains("Shared project docs"));
-        assert!(json.contains("1F4C1"));
-        assert!(json.contains("#4F46E5"));
+        assert!(client
+            .check_content(Some("# Title\r\n\n- [ ] task\twith tab 🚀\n"))
+            .is_ok());
+        assert!(client.check_content(None).is_ok());
+
+        match client.check_content(Some("PK\u{3}\u{4}\0\0binary")) {
+            Err(ApiError::Validation(err)) => assert!(err.message.contains("byte 2")),
+            other => panic!("expected validation error, got {other:?}"),
+        }
+
+        let permissive = ApiClient::new("test_token").unwrap();
+        assert!(permissive.check_content(Some("null\0byte")).is_ok());
+    }
+
+    fn snake_case_keys(value: Value) -> Value {
+        match value {
+            Value::Object(map) => Value::Object(
+                map.into_iter()
+                    .map(|(key, value)| {
+                        let key = key.chars().fold(String::new(), |mut snake, c| {
+                            if c.is_ascii_uppercase() {
+                                snake.push('_');
+                            }
+                            snake.push(c.to_ascii_lowercase());
+                            snake
+                        });
+                        (key, snake_case_keys(value))
+                    })
+                    .collect(),
+            ),
+            Value::Array(items) => Value::Array(items.into_iter().map(snake_case_keys).collect()),
+            other => other,
+        }
     }
 
     #[test]
-    fn test_note_deserialization_accepts_double_millisecond_timestamps() {
-        let value = serde_json::json!({
-            "id": "note-123",
-            "title": "Timestamp Note",
-            "description": "",
-            "tags": [],
-            "lastChangedAt": 1_710_000_000_000.0,
-            "createdAt": 1_710_000_000_000.0,
-            "titleUpdatedAt": 1_710_000_000_001.0,
-            "tagsUpdatedAt": null,
-            "lastChangeUser": null,
-            "publishType": "edit",
-            "publishedAt": null,
-            "userPath": "demo-user",
-            "teamPath": null,
-            "permalink": null,
-            "shortId": "short-123",
-            "publishLink": "https://hackmd.io/note-123",
-            "folderPaths": [],
-            "readPermission": "owner",
-            "writePermission": "owner"
+    fn test_read_types_accept_snake_case_field_aliases() {
+        let mut note = sample_single_note();
+        note.note.last_change_user = Some(SimpleUserProfile {
+            name: "Editor".to_string(),
+            user_path: "editor".to_string(),
+            photo: "https://hackmd.io/editor.png".to_string(),
+            biography: None,
         });
+        let json = snake_case_keys(serde_json::to_value(&note).unwrap());
 
-        let note: Note = serde_json::from_value(value).unwrap();
+        assert_eq!(json["last_changed_at"], 1_710_000_000_000i64);
+        assert_eq!(json["last_change_user"]["user_path"], "editor");
+        assert_eq!(serde_json::from_value::<SingleNote>(json).unwrap(), note);
 
-        assert_eq!(note.id, "note-123");
-        assert!(note.title_updated_at.is_some());
+        let user = sample_user(&["platform-team"]);
+        let json = snake_case_keys(serde_json::to_value(&user).unwrap());
+
+        assert_eq!(json["user_path"], "demo-user");
+        assert_eq!(json["teams"][0]["owner_id"], user.teams[0].owner_id);
+        assert_eq!(serde_json::from_value::<User>(json).unwrap(), user);
     }
 
     #[test]
-    fn test_update_folder_options_serialization_supports_null_clears() {
-        let options = UpdateFolderOptions {
-            name: Some("Renamed Folder".to_string()),
-            parent_folder_id: Some(None),
-            description: Some(None),
-            icon: None,
-            color: Some(Some("#16A34A".to_string())),
-        };
+    fn test_note_tags_match_case_insensitively() {
+        let mut note = sample_single_note().note;
+        note.tags = vec![
+            "Rust".to_string(),
+            " API-Client ".to_string(),
+            "rust".to_string(),
+            "".to_string(),
+            "Ünïcode".to_string(),
+        ];
 
-        let json = serde_json::to_value(&options).unwrap();
-        assert_eq!(json["name"], "Renamed Folder");
-        assert_eq!(json["parentFolderId"], Value::Null);
-        assert_eq!(json["description"], Value::Null);
-        assert_eq!(json["color"], "#16A34A");
-        assert!(json.get("icon").is_none());
+        assert!(note.has_tag("rust"));
+        assert!(note.has_tag("RUST"));
+        assert!(note.has_tag("api-client"));
+        assert!(note.has_tag("  Api-Client"));
+        assert!(note.has_tag("ÜNÏCODE"));
+        assert!(!note.has_tag("api"));
+        assert!(!note.has_tag(" "));
+        assert_eq!(
+            note.normalized_tags(),
+            vec!["rust", "api-client", "ünïcode"]
+        );
     }
 
     #[test]
-    fn test_update_folder_order_options_serialization() {
-        let options = UpdateFolderOrderOptions {
-            order: std::collections::BTreeMap::from([
-                (
-                    "root".to_string(),
-                    vec!["folder-a".to_string(), "folder-b".to_string()],
-                ),
-                (
-                    "folder-a".to_string(),
-                    vec!["folder-c".to_string(), "folder-d".to_string()],
-                ),
-            ]),
+    fn test_auto_title_extracts_leading_h1_without_touching_content() {
+        let client = ApiClient::with_options(
+            "test_token",
+            None,
+            Some(ApiClientOptions {
+                auto_title: true,
+                ..Default::default()
+            }),
+        )
+        .unwrap();
+        let create = |title: Option<&str>, content: &str| CreateNoteOptions {
+            title: title.map(str::to_string),
+            content: Some(content.to_string()),
+            ..Default::default()
         };
 
-        let json = serde_json::to_value(&options).unwrap();
+        let payload = create(None, "\n# Weekly Sync ##\n\nAgenda");
+        let normalized = client.normalized_create_payload(&payload);
+        assert_eq!(normalized.title.as_deref(), Some("Weekly Sync"));
+        assert_eq!(normalized.content, payload.content);
+
+        let payload = create(Some("Explicit"), "# Heading");
         assert_eq!(
-            json["order"]["root"],
-            serde_json::json!(["folder-a", "folder-b"])
+            client.normalized_create_payload(&payload).title.as_deref(),
+            Some("Explicit")
         );
+
+        for content in [
+            "Intro\n# Later heading",
+            "## Subheading",
+            "#NoSpace",
+            "#   ",
+        ] {
+            let payload = create(None, content);
+            assert_eq!(client.normalized_create_payload(&payload).title, None);
+        }
+
+        let payload = create(None, "# Heading");
+        let disabled = ApiClient::new("test_token").unwrap();
+        assert_eq!(disabled.normalized_create_payload(&payload).title, None);
+    }
+
+    #[test]
+    fn test_note_last_change_user_accepts_null_missing_and_partial_objects() {
+        let mut json = serde_json::to_value(sample_single_note().note).unwrap();
+
+        json["lastChangeUser"] = Value::Null;
+        let note: Note = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(note.last_change_user, None);
+
+        json.as_object_mut().unwrap().remove("lastChangeUser");
+        let note: Note = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(note.last_change_user, None);
+
+        json["lastChangeUser"] = serde_json::json!({
+            "name": "Editor",
+            "userPath": "editor",
+            "photo": "https://hackmd.io/editor.png"
+        });
+        let note: Note = serde_json::from_value(json).unwrap();
         assert_eq!(
-            json["order"]["folder-a"],
-            serde_json::json!(["folder-c", "folder-d"])
+            note.last_change_user,
+            Some(SimpleUserProfile {
+                name: "Editor".to_string(),
+                user_path: "editor".to_string(),
+                photo: "https://hackmd.io/editor.png".to_string(),
+                biography: None,
+            })
         );
     }
 
     #[test]
-    fn test_rate_limit_errors_are_retryable() {
-        let client = ApiClient::new("test_token").unwrap();
-        let error = ApiError::TooManyRequests(TooManyRequestsError {
-            message: "Too many requests".to_string(),
-            code: 429,
-            status_text: "Too Many Requests".to_string(),
-            user_limit: 60,
-            user_remaining: 0,
-            reset_after: Some(1),
-        });
+    fn test_to_request_json_omits_unset_fields() {
+        let create = CreateNoteOptions {
+            title: Some("Debug".to_string()),
+            read_permission: Some(NotePermissionRole::SignedIn),
+            idempotency_key: Some("not-sent".to_string()),
+            ..Default::default()
+        };
+        let json: Value = serde_json::from_str(&create.to_request_json().unwrap()).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({ "title": "Debug", "readPermission": "signed_in" })
+        );
+
+        let update = UpdateNoteOptions {
+            content: Some(String::new()),
+            ..Default::default()
+        };
+        assert_eq!(update.to_request_json().unwrap(), r#"{"content":""}"#);
+        assert_eq!(
+            UpdateNoteOptions::default().to_request_json().unwrap(),
+            "{}"
+        );
+    }
+
+    #[test]
+    fn test_unwrap_data_envelope_accepts_enveloped_and_bare_bodies() {
+        let user = serde_json::to_value(sample_user(&["platform-team"])).unwrap();
+
+        let enveloped = serde_json::json!({ "data": user.clone() });
+        let unwrapped: User = ApiClient::unwrap_data_envelope(enveloped).unwrap();
+        assert_eq!(unwrapped.user_path, "demo-user");
+
+        let bare: User = ApiClient::unwrap_data_envelope(user).unwrap();
+        assert_eq!(bare.teams.len(), 1);
+
+        let upload = serde_json::json!({ "data": { "link": "https://hackmd.io/_uploads/a.png" } });
+        let response: NoteImageUploadResponse = ApiClient::unwrap_data_envelope(upload).unwrap();
+        assert_eq!(response.data.link, "https://hackmd.io/_uploads/a.png");
 
-        assert!(client.is_retryable_error(&error));
+        assert!(matches!(
+            ApiClient::unwrap_data_envelope::<User>(serde_json::json!({ "data": [] })),
+            Err(ApiError::Serde(_))
+        ));
     }
 
     #[test]
-    fn test_success_status_accepts_all_2xx_codes() {
-        assert!(ApiClient::is_success_status(StatusCode::OK));
-        assert!(ApiClient::is_success_status(StatusCode::ACCEPTED));
-        assert!(ApiClient::is_success_status(StatusCode::NO_CONTENT));
-        assert!(!ApiClient::is_success_status(StatusCode::BAD_REQUEST));
+    fn test_options_validation_checks_endpoint_retry_overrides() {
+        assert_invalid_options(
+            ApiClientOptions {
+                endpoint_retry_overrides: HashMap::from([("delete_notes", None)]),
+                ..Default::default()
+            },
+            "unknown endpoint \"delete_notes\"",
+        );
+        assert_invalid_options(
+            ApiClientOptions {
+                endpoint_retry_overrides: HashMap::from([(
+                    "get_note",
+                    Some(RetryOptions {
+                        max_retries: 0,
+                        ..Default::default()
+                    }),
+                )]),
+                ..Default::default()
+            },
+            "endpoint_retry_overrides[\"get_note\"].max_retries",
+        );
+
+        for endpoint in RETRY_ENDPOINTS {
+            let options = ApiClientOptions {
+                endpoint_retry_overrides: HashMap::from([(*endpoint, None)]),
+                ..Default::default()
+            };
+            assert!(options.validate().is_ok(), "{endpoint} should be accepted");
+        }
     }
 
     #[test]
-    fn test_exponential_backoff_doubles_between_attempts() {
-        let client = ApiClient::new("test_token").unwrap();
-        let base_delay = time::Duration::from_millis(100);
+    fn test_create_options_from_single_note_keeps_content_permissions_and_permalink() {
+        let mut note = sample_single_note();
+        note.note.description = "Migrated".to_string();
+        note.note.permalink = Some("weekly-sync".to_string());
+        note.note.read_permission = NotePermissionRole::Guest;
+        note.note.write_permission = NotePermissionRole::SignedIn;
+
+        let options = CreateNoteOptions::from(&note);
 
-        assert_eq!(client.exponential_backoff(0, base_delay), base_delay);
-        assert_eq!(
-            client.exponential_backoff(1, base_delay),
-            time::Duration::from_millis(200)
-        );
         assert_eq!(
-            client.exponential_backoff(2, base_delay),
-            time::Duration::from_millis(400)
+            options,
+            CreateNoteOptions {
+                title: Some(note.note.title.clone()),
+                content: Some("# Sample".to_string()),
+                description: Some("Migrated".to_string()),
+                tags: Some(vec!["rust".to_string()]),
+                read_permission: Some(NotePermissionRole::Guest),
+                write_permission: Some(NotePermissionRole::SignedIn),
+                permalink: Some("weekly-sync".to_string()),
+                ..Default::default()
+            }
         );
+        assert_eq!(note.to_create_options().permalink, None);
+    }
+
+    #[test]
+    fn test_batch_updater_requires_a_tokio_runtime() {
+        let client = ApiClient::new("test_token").unwrap();
+        let error = client
+            .batch_updater(time::Duration::from_millis(50))
+            .err()
+            .unwrap();
+
+        assert!(matches!(error, ApiError::HackMD(_)));
+        assert!(error.to_string().contains("Tokio runtime"));
     }
 }
@@ -0,0 +1,10 @@
+//! Commonly used types, for `use hackmd_api_client_rs::prelude::*;`. The crate's `Result`
+//! alias is left out so the glob import does not shadow `std::result::Result`.
+
+pub use crate::error::ApiError;
+pub use crate::{
+    ApiClient, ApiClientOptions, BatchUpdater, CommentPermissionType, CreateFolderOptions,
+    CreateNoteOptions, Folder, Note, NoteContentType, NotePermissionRole, NotePublishType,
+    RateLimit, RetryOptions, SingleNote, SuggestEditPermissionType, Team, TeamClient,
+    TeamVisibilityType, UpdateFolderOptions, UpdateNoteOptions, User,
+};
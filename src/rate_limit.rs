@@ -0,0 +1,48 @@
+use std::sync::{Mutex, PoisonError};
+use std::time;
+
+struct Bucket {
+    tokens: f64,
+    refilled_at: time::Instant,
+}
+
+pub(crate) struct RateLimiter {
+    capacity: f64,
+    tokens_per_second: f64,
+    bucket: Mutex<Bucket>,
+}
+
+impl RateLimiter {
+    pub(crate) fn new(max_requests: u32, per: time::Duration) -> Self {
+        let capacity = f64::from(max_requests);
+        Self {
+            capacity,
+            tokens_per_second: capacity / per.as_secs_f64(),
+            bucket: Mutex::new(Bucket {
+                tokens: capacity,
+                refilled_at: time::Instant::now(),
+            }),
+        }
+    }
+
+    pub(crate) async fn acquire(&self) {
+        loop {
+            let wait = {
+                let mut bucket = self.bucket.lock().unwrap_or_else(PoisonError::into_inner);
+                let now = time::Instant::now();
+                let elapsed = now.duration_since(bucket.refilled_at).as_secs_f64();
+                bucket.tokens =
+                    (bucket.tokens + elapsed * self.tokens_per_second).min(self.capacity);
+                bucket.refilled_at = now;
+
+                if bucket.tokens >= 1.0 {
+                    bucket.tokens -= 1.0;
+                    return;
+                }
+
+                time::Duration::from_secs_f64((1.0 - bucket.tokens) / self.tokens_per_second)
+            };
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
@@ -0,0 +1,88 @@
+use reqwest::{header, Response, StatusCode};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, PoisonError};
+use tokio::sync::oneshot;
+
+#[derive(Clone)]
+pub(crate) struct BufferedResponse {
+    pub(crate) status: StatusCode,
+    pub(crate) headers: header::HeaderMap,
+    pub(crate) body: bytes::Bytes,
+}
+
+impl BufferedResponse {
+    pub(crate) fn into_response(self) -> Response {
+        let mut response = http::Response::new(self.body);
+        *response.status_mut() = self.status;
+        *response.headers_mut() = self.headers;
+        Response::from(response)
+    }
+}
+
+type Waiters = Vec<oneshot::Sender<Option<BufferedResponse>>>;
+
+#[derive(Default)]
+pub(crate) struct SingleFlight {
+    in_flight: Mutex<HashMap<String, Waiters>>,
+}
+
+pub(crate) enum Flight {
+    Leader(FlightGuard),
+    Follower(oneshot::Receiver<Option<BufferedResponse>>),
+}
+
+impl SingleFlight {
+    pub(crate) fn join(self: &Arc<Self>, key: String) -> Flight {
+        let mut in_flight = self
+            .in_flight
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        match in_flight.get_mut(&key) {
+            Some(waiters) => {
+                let (sender, receiver) = oneshot::channel();
+                waiters.push(sender);
+                Flight::Follower(receiver)
+            }
+            None => {
+                in_flight.insert(key.clone(), Vec::new());
+                Flight::Leader(FlightGuard {
+                    single_flight: Arc::clone(self),
+                    key,
+                    armed: true,
+                })
+            }
+        }
+    }
+
+    fn take_waiters(&self, key: &str) -> Waiters {
+        // Also reached from `FlightGuard::drop`, where a poisoned-lock panic would abort.
+        self.in_flight
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(key)
+            .unwrap_or_default()
+    }
+}
+
+pub(crate) struct FlightGuard {
+    single_flight: Arc<SingleFlight>,
+    key: String,
+    armed: bool,
+}
+
+impl FlightGuard {
+    pub(crate) fn complete(mut self, response: Option<BufferedResponse>) {
+        self.armed = false;
+        for waiter in self.single_flight.take_waiters(&self.key) {
+            let _ = waiter.send(response.clone());
+        }
+    }
+}
+
+impl Drop for FlightGuard {
+    fn drop(&mut self) {
+        if self.armed {
+            self.single_flight.take_waiters(&self.key);
+        }
+    }
+}
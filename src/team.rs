@@ -0,0 +1,41 @@
+use crate::{ApiClient, CreateNoteOptions, Note, Result, SingleNote, UpdateNoteOptions};
+
+pub struct TeamClient<'a> {
+    client: &'a ApiClient,
+    team_path: String,
+}
+
+impl<'a> TeamClient<'a> {
+    pub(crate) fn new(client: &'a ApiClient, team_path: impl Into<String>) -> Self {
+        Self {
+            client,
+            team_path: team_path.into(),
+        }
+    }
+
+    pub fn team_path(&self) -> &str {
+        &self.team_path
+    }
+
+    pub async fn notes(&self) -> Result<Vec<Note>> {
+        self.client.get_team_notes(&self.team_path).await
+    }
+
+    pub async fn get_note(&self, note_id: &str) -> Result<SingleNote> {
+        self.client.get_team_note(&self.team_path, note_id).await
+    }
+
+    pub async fn create_note(&self, payload: &CreateNoteOptions) -> Result<SingleNote> {
+        self.client.create_team_note(&self.team_path, payload).await
+    }
+
+    pub async fn update_note(&self, note_id: &str, payload: &UpdateNoteOptions) -> Result<()> {
+        self.client
+            .update_team_note(&self.team_path, note_id, payload)
+            .await
+    }
+
+    pub async fn delete_note(&self, note_id: &str) -> Result<()> {
+        self.client.delete_team_note(&self.team_path, note_id).await
+    }
+}
@@ -0,0 +1,131 @@
+use crate::{Note, NotePermissionRole, NotePublishType, SimpleUserProfile, SingleNote};
+use chrono::{DateTime, Utc};
+
+const FIXTURE_TIMESTAMP_MILLIS: i64 = 1_710_000_000_000;
+
+#[derive(Debug, Clone)]
+pub struct NoteFixture {
+    note: Note,
+    content: String,
+}
+
+impl Default for NoteFixture {
+    fn default() -> Self {
+        let timestamp = DateTime::from_timestamp_millis(FIXTURE_TIMESTAMP_MILLIS).unwrap();
+
+        Self {
+            note: Note {
+                id: "note-123".to_string(),
+                title: "Sample Note".to_string(),
+                description: String::new(),
+                tags: Vec::new(),
+                last_changed_at: timestamp,
+                created_at: timestamp,
+                title_updated_at: None,
+                tags_updated_at: None,
+                last_change_user: None,
+                publish_type: NotePublishType::Edit,
+                published_at: None,
+                user_path: Some("demo-user".to_string()),
+                team_path: None,
+                permalink: None,
+                short_id: "short-123".to_string(),
+                publish_link: "https://hackmd.io/note-123".to_string(),
+                folder_paths: Vec::new(),
+                read_permission: NotePermissionRole::Owner,
+                write_permission: NotePermissionRole::Owner,
+            },
+            content: String::new(),
+        }
+    }
+}
+
+impl NoteFixture {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn id(mut self, id: &str) -> Self {
+        self.note.id = id.to_string();
+        self.note.publish_link = format!("https://hackmd.io/{id}");
+        self
+    }
+
+    pub fn short_id(mut self, short_id: &str) -> Self {
+        self.note.short_id = short_id.to_string();
+        self
+    }
+
+    pub fn title(mut self, title: &str) -> Self {
+        self.note.title = title.to_string();
+        self
+    }
+
+    pub fn description(mut self, description: &str) -> Self {
+        self.note.description = description.to_string();
+        self
+    }
+
+    pub fn tags(mut self, tags: &[&str]) -> Self {
+        self.note.tags = tags.iter().map(|tag| tag.to_string()).collect();
+        self
+    }
+
+    pub fn content(mut self, content: &str) -> Self {
+        self.content = content.to_string();
+        self
+    }
+
+    pub fn team_path(mut self, team_path: &str) -> Self {
+        self.note.team_path = Some(team_path.to_string());
+        self.note.user_path = None;
+        self
+    }
+
+    pub fn last_changed_at(mut self, last_changed_at: DateTime<Utc>) -> Self {
+        self.note.last_changed_at = last_changed_at;
+        self
+    }
+
+    pub fn last_change_user(mut self, user_path: &str) -> Self {
+        self.note.last_change_user = Some(SimpleUserProfile {
+            name: user_path.to_string(),
+            user_path: user_path.to_string(),
+            photo: "https://hackmd.io/photo.png".to_string(),
+            biography: None,
+        });
+        self
+    }
+
+    pub fn publish_type(mut self, publish_type: NotePublishType) -> Self {
+        self.note.publish_type = publish_type;
+        self
+    }
+
+    pub fn published_at(mut self, published_at: DateTime<Utc>) -> Self {
+        self.note.published_at = Some(published_at);
+        self
+    }
+
+    pub fn permalink(mut self, permalink: &str) -> Self {
+        self.note.permalink = Some(permalink.to_string());
+        self
+    }
+
+    pub fn permissions(mut self, read: NotePermissionRole, write: NotePermissionRole) -> Self {
+        self.note.read_permission = read;
+        self.note.write_permission = write;
+        self
+    }
+
+    pub fn build_note(self) -> Note {
+        self.note
+    }
+
+    pub fn build_single_note(self) -> SingleNote {
+        SingleNote {
+            content: self.content,
+            note: self.note,
+        }
+    }
+}
@@ -1,7 +1,10 @@
+use crate::error::{ApiError, ValidationError};
 use chrono::{DateTime, TimeZone, Utc};
-use serde::{de, Deserialize, Deserializer, Serialize};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 use serde_json::Value;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+const DEFAULT_SITE_ORIGIN: &str = "https://hackmd.io";
 
 #[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
 #[serde(rename_all = "lowercase")]
@@ -19,11 +22,30 @@ pub enum NotePublishType {
     Book,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NoteContentType {
+    #[default]
+    Json,
+    Markdown,
+    Html,
+}
+
+impl NoteContentType {
+    pub fn mime_type(&self) -> &'static str {
+        match self {
+            NoteContentType::Json => "application/json",
+            NoteContentType::Markdown => "text/markdown",
+            NoteContentType::Html => "text/html",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default, Deserialize, Serialize)]
 #[serde(rename_all = "snake_case")]
 pub enum CommentPermissionType {
     Disabled,
     Forbidden,
+    #[default]
     Owners,
     SignedInUsers,
     Everyone,
@@ -38,14 +60,105 @@ pub enum SuggestEditPermissionType {
     SignedInUsers,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Deserialize, Serialize)]
 #[serde(rename_all = "snake_case")]
 pub enum NotePermissionRole {
+    #[default]
     Owner,
     SignedIn,
     Guest,
 }
 
+impl NotePermissionRole {
+    fn openness(&self) -> u8 {
+        match self {
+            NotePermissionRole::Owner => 0,
+            NotePermissionRole::SignedIn => 1,
+            NotePermissionRole::Guest => 2,
+        }
+    }
+}
+
+impl CommentPermissionType {
+    fn required_read_openness(&self) -> u8 {
+        match self {
+            CommentPermissionType::Disabled
+            | CommentPermissionType::Forbidden
+            | CommentPermissionType::Owners => 0,
+            CommentPermissionType::SignedInUsers => 1,
+            CommentPermissionType::Everyone => 2,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct NotePermissions {
+    pub read_permission: Option<NotePermissionRole>,
+    pub write_permission: Option<NotePermissionRole>,
+    pub comment_permission: Option<CommentPermissionType>,
+}
+
+impl NotePermissions {
+    pub fn builder() -> NotePermissionsBuilder {
+        NotePermissionsBuilder::default()
+    }
+
+    pub fn apply_to(&self, options: &mut CreateNoteOptions) {
+        options.read_permission = self.read_permission.clone();
+        options.write_permission = self.write_permission.clone();
+        options.comment_permission = self.comment_permission.clone();
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct NotePermissionsBuilder {
+    permissions: NotePermissions,
+}
+
+impl NotePermissionsBuilder {
+    pub fn read(mut self, role: NotePermissionRole) -> Self {
+        self.permissions.read_permission = Some(role);
+        self
+    }
+
+    pub fn write(mut self, role: NotePermissionRole) -> Self {
+        self.permissions.write_permission = Some(role);
+        self
+    }
+
+    pub fn comment(mut self, permission: CommentPermissionType) -> Self {
+        self.permissions.comment_permission = Some(permission);
+        self
+    }
+
+    pub fn build(self) -> Result<NotePermissions, ApiError> {
+        let invalid = |message: String| Err(ApiError::Validation(ValidationError { message }));
+        let NotePermissions {
+            read_permission,
+            write_permission,
+            comment_permission,
+        } = &self.permissions;
+
+        if let (Some(read), Some(write)) = (read_permission, write_permission) {
+            if write.openness() > read.openness() {
+                return invalid(format!(
+                    "write permission {write:?} is more open than read permission {read:?}"
+                ));
+            }
+        }
+
+        if let (Some(read), Some(comment)) = (read_permission, comment_permission) {
+            if comment.required_read_openness() > read.openness() {
+                return invalid(format!(
+                    "comment permission {comment:?} is more open than read permission {read:?}"
+                ));
+            }
+        }
+
+        Ok(self.permissions)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct FolderPath {
@@ -96,7 +209,59 @@ where
         .transpose()
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+fn serialize_ts_milliseconds<S>(value: &DateTime<Utc>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_i64(value.timestamp_millis())
+}
+
+fn serialize_ts_milliseconds_option<S>(
+    value: &Option<DateTime<Utc>>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    match value {
+        Some(value) => serializer.serialize_some(&value.timestamp_millis()),
+        None => serializer.serialize_none(),
+    }
+}
+
+#[derive(Serialize)]
+#[serde(untagged)]
+enum CanonicalValue {
+    Object(BTreeMap<String, CanonicalValue>),
+    Array(Vec<CanonicalValue>),
+    Scalar(Value),
+}
+
+impl From<Value> for CanonicalValue {
+    fn from(value: Value) -> Self {
+        match value {
+            Value::Object(map) => CanonicalValue::Object(
+                map.into_iter()
+                    .map(|(key, value)| (key, value.into()))
+                    .collect(),
+            ),
+            Value::Array(values) => {
+                CanonicalValue::Array(values.into_iter().map(Into::into).collect())
+            }
+            scalar => CanonicalValue::Scalar(scalar),
+        }
+    }
+}
+
+fn to_canonical_json<T>(value: &T) -> serde_json::Result<String>
+where
+    T: Serialize,
+{
+    let value = CanonicalValue::from(serde_json::to_value(value)?);
+    serde_json::to_string(&value)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Folder {
     pub id: String,
@@ -105,9 +270,15 @@ pub struct Folder {
     pub icon: Option<String>,
     pub color: Option<String>,
     pub parent_folder_id: Option<String>,
-    #[serde(deserialize_with = "deserialize_ts_milliseconds")]
+    #[serde(
+        deserialize_with = "deserialize_ts_milliseconds",
+        serialize_with = "serialize_ts_milliseconds"
+    )]
     pub created_at: DateTime<Utc>,
-    #[serde(deserialize_with = "deserialize_ts_milliseconds")]
+    #[serde(
+        deserialize_with = "deserialize_ts_milliseconds",
+        serialize_with = "serialize_ts_milliseconds"
+    )]
     pub updated_at: DateTime<Utc>,
 }
 
@@ -147,7 +318,7 @@ pub struct UpdateFolderOrderOptions {
     pub order: FolderOrder,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Default)]
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct CreateNoteOptions {
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -174,55 +345,180 @@ pub struct CreateNoteOptions {
     pub parent_folder_id: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub origin: Option<String>,
+    /// Sent as the `Idempotency-Key` header rather than in the body.
+    #[serde(skip)]
+    pub idempotency_key: Option<String>,
+}
+
+fn render_template(
+    template: &str,
+    vars: &HashMap<String, String>,
+    strict: bool,
+) -> Result<String, ApiError> {
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        let placeholder = &rest[start..start + 2 + end + 2];
+        let key = placeholder[2..placeholder.len() - 2].trim();
+
+        rendered.push_str(&rest[..start]);
+        match vars.get(key) {
+            Some(value) => rendered.push_str(value),
+            None if strict => {
+                return Err(ApiError::Validation(ValidationError {
+                    message: format!("Unresolved template placeholder: {placeholder}"),
+                }))
+            }
+            None => rendered.push_str(placeholder),
+        }
+        rest = &rest[start + placeholder.len()..];
+    }
+
+    rendered.push_str(rest);
+    Ok(rendered)
+}
+
+impl CreateNoteOptions {
+    pub fn to_canonical_json(&self) -> serde_json::Result<String> {
+        to_canonical_json(self)
+    }
+
+    /// The JSON body `ApiClient` sends for these options, with unset fields omitted. Client
+    /// options such as `normalize_line_endings` or `auto_title` may still adjust it.
+    pub fn to_request_json(&self) -> Result<String, ApiError> {
+        Ok(serde_json::to_string(self)?)
+    }
+
+    /// Only the owner can read, write, or comment.
+    pub fn private() -> Self {
+        Self {
+            read_permission: Some(NotePermissionRole::Owner),
+            write_permission: Some(NotePermissionRole::Owner),
+            comment_permission: Some(CommentPermissionType::Owners),
+            ..Default::default()
+        }
+    }
+
+    /// Anyone with the link can read, signed-in users can comment, and only the owner can write.
+    pub fn public() -> Self {
+        Self {
+            read_permission: Some(NotePermissionRole::Guest),
+            write_permission: Some(NotePermissionRole::Owner),
+            comment_permission: Some(CommentPermissionType::SignedInUsers),
+            ..Default::default()
+        }
+    }
+
+    /// Builds options whose content is `template` with every `{{key}}` replaced from
+    /// `vars`. Fails with `ApiError::Validation` if a placeholder has no value.
+    pub fn from_template(template: &str, vars: &HashMap<String, String>) -> Result<Self, ApiError> {
+        Ok(Self {
+            content: Some(render_template(template, vars, true)?),
+            ..Default::default()
+        })
+    }
+
+    /// Like `from_template`, but leaves unresolved placeholders in the content as-is.
+    pub fn from_template_lenient(template: &str, vars: &HashMap<String, String>) -> Self {
+        let content = render_template(template, vars, false).unwrap_or_else(|_| template.into());
+        Self {
+            content: Some(content),
+            ..Default::default()
+        }
+    }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
 pub struct NoteImageUploadData {
     pub link: String,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
 pub struct NoteImageUploadResponse {
     pub data: NoteImageUploadData,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Team {
     pub id: String,
+    #[serde(alias = "owner_id")]
     pub owner_id: String,
     pub name: String,
     pub logo: String,
     pub path: String,
     pub description: Option<String>,
     pub visibility: TeamVisibilityType,
-    #[serde(deserialize_with = "deserialize_ts_milliseconds")]
+    #[serde(
+        deserialize_with = "deserialize_ts_milliseconds",
+        serialize_with = "serialize_ts_milliseconds",
+        alias = "created_at"
+    )]
     pub created_at: DateTime<Utc>,
     pub upgraded: bool,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct User {
     pub id: String,
     pub email: Option<String>,
     pub name: String,
+    #[serde(alias = "user_path")]
     pub user_path: String,
     pub photo: String,
     pub teams: Vec<Team>,
     pub upgraded: bool,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+impl User {
+    pub fn team_map(&self) -> HashMap<String, &Team> {
+        self.teams
+            .iter()
+            .map(|team| (team.path.clone(), team))
+            .collect()
+    }
+
+    pub fn team_by_path(&self, path: &str) -> Option<&Team> {
+        self.teams.iter().find(|team| team.path == path)
+    }
+
+    pub fn photo_url(&self, base: &url::Url) -> Option<url::Url> {
+        resolve_asset_url(&self.photo, base)
+    }
+}
+
+impl Team {
+    pub fn logo_url(&self, base: &url::Url) -> Option<url::Url> {
+        resolve_asset_url(&self.logo, base)
+    }
+}
+
+/// Resolves an absolute or relative asset URL against `base`, or `None` if it is empty or
+/// cannot be parsed.
+fn resolve_asset_url(value: &str, base: &url::Url) -> Option<url::Url> {
+    let value = value.trim();
+    if value.is_empty() {
+        return None;
+    }
+    base.join(value).ok()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SimpleUserProfile {
     pub name: String,
+    #[serde(alias = "user_path")]
     pub user_path: String,
     pub photo: String,
     pub biography: Option<String>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Note {
     pub id: String,
@@ -230,30 +526,192 @@ pub struct Note {
     #[serde(default)]
     pub description: String,
     pub tags: Vec<String>,
-    #[serde(deserialize_with = "deserialize_ts_milliseconds")]
+    #[serde(
+        deserialize_with = "deserialize_ts_milliseconds",
+        serialize_with = "serialize_ts_milliseconds",
+        alias = "last_changed_at"
+    )]
     pub last_changed_at: DateTime<Utc>,
-    #[serde(deserialize_with = "deserialize_ts_milliseconds")]
+    #[serde(
+        deserialize_with = "deserialize_ts_milliseconds",
+        serialize_with = "serialize_ts_milliseconds",
+        alias = "created_at"
+    )]
     pub created_at: DateTime<Utc>,
-    #[serde(deserialize_with = "deserialize_ts_milliseconds_option")]
+    #[serde(
+        default,
+        deserialize_with = "deserialize_ts_milliseconds_option",
+        serialize_with = "serialize_ts_milliseconds_option",
+        alias = "title_updated_at"
+    )]
     pub title_updated_at: Option<DateTime<Utc>>,
-    #[serde(deserialize_with = "deserialize_ts_milliseconds_option")]
+    #[serde(
+        default,
+        deserialize_with = "deserialize_ts_milliseconds_option",
+        serialize_with = "serialize_ts_milliseconds_option",
+        alias = "tags_updated_at"
+    )]
     pub tags_updated_at: Option<DateTime<Utc>>,
+    #[serde(alias = "last_change_user")]
     pub last_change_user: Option<SimpleUserProfile>,
+    #[serde(alias = "publish_type")]
     pub publish_type: NotePublishType,
-    #[serde(deserialize_with = "deserialize_ts_milliseconds_option")]
+    #[serde(
+        default,
+        deserialize_with = "deserialize_ts_milliseconds_option",
+        serialize_with = "serialize_ts_milliseconds_option",
+        alias = "published_at"
+    )]
     pub published_at: Option<DateTime<Utc>>,
+    #[serde(alias = "user_path")]
     pub user_path: Option<String>,
+    #[serde(alias = "team_path")]
     pub team_path: Option<String>,
     pub permalink: Option<String>,
+    #[serde(alias = "short_id")]
     pub short_id: String,
+    #[serde(alias = "publish_link")]
     pub publish_link: String,
-    #[serde(default)]
+    #[serde(default, alias = "folder_paths")]
     pub folder_paths: Vec<FolderPath>,
+    #[serde(alias = "read_permission")]
     pub read_permission: NotePermissionRole,
+    #[serde(alias = "write_permission")]
     pub write_permission: NotePermissionRole,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+impl Note {
+    pub fn content_eq(&self, other: &Note) -> bool {
+        self.id == other.id
+            && self.title == other.title
+            && self.description == other.description
+            && self.tags == other.tags
+            && self.read_permission == other.read_permission
+            && self.write_permission == other.write_permission
+            && self.publish_type == other.publish_type
+            && self.is_published() == other.is_published()
+            && self.permalink == other.permalink
+            && self.publish_link == other.publish_link
+            && self.folder_paths == other.folder_paths
+    }
+
+    pub fn is_published(&self) -> bool {
+        self.published_at.is_some()
+    }
+
+    pub fn is_slide(&self) -> bool {
+        self.publish_type == NotePublishType::Slide
+    }
+
+    pub fn is_book(&self) -> bool {
+        self.publish_type == NotePublishType::Book
+    }
+
+    /// Owners can always write. Otherwise `SignedIn` and `Guest` write permissions are open
+    /// to any signed-in user, and `Owner` is not. Team notes are owned by team members.
+    pub fn is_writable_by(&self, user: &User) -> bool {
+        let is_owner = self.user_path.as_deref() == Some(user.user_path.as_str())
+            || self
+                .team_path
+                .as_deref()
+                .is_some_and(|team_path| user.team_by_path(team_path).is_some());
+
+        is_owner || self.write_permission != NotePermissionRole::Owner
+    }
+
+    /// Matches `tag` against the note's tags ignoring case and surrounding whitespace.
+    pub fn has_tag(&self, tag: &str) -> bool {
+        let tag = normalize_tag(tag);
+        !tag.is_empty() && self.tags.iter().any(|t| normalize_tag(t) == tag)
+    }
+
+    /// Trimmed, lowercased tags in their original order, without blanks or duplicates.
+    pub fn normalized_tags(&self) -> Vec<String> {
+        let mut seen = HashSet::new();
+        self.tags
+            .iter()
+            .map(|tag| normalize_tag(tag))
+            .filter(|tag| !tag.is_empty() && seen.insert(tag.clone()))
+            .collect()
+    }
+
+    pub fn is_last_changed_by(&self, user_path: &str) -> bool {
+        self.last_change_user
+            .as_ref()
+            .is_some_and(|user| user.user_path == user_path)
+    }
+}
+
+/// Returns the text of a Markdown `# Heading` on the first non-blank line of `content`.
+pub(crate) fn leading_h1(content: &str) -> Option<String> {
+    let line = content.lines().find(|line| !line.trim().is_empty())?;
+    let title = line.trim_start().strip_prefix("# ")?;
+    let title = title.trim().trim_end_matches('#').trim_end();
+    (!title.is_empty()).then(|| title.to_string())
+}
+
+fn normalize_tag(tag: &str) -> String {
+    tag.trim().to_lowercase()
+}
+
+/// Wrapper that compares and hashes a `Note` by `id` only, so notes can be deduplicated in a
+/// `HashSet` even when their timestamps or other fields differ.
+#[derive(Debug, Clone)]
+pub struct NoteRef(pub Note);
+
+impl PartialEq for NoteRef {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.id == other.0.id
+    }
+}
+
+impl Eq for NoteRef {}
+
+impl std::hash::Hash for NoteRef {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.0.id.hash(state);
+    }
+}
+
+impl From<Note> for NoteRef {
+    fn from(note: Note) -> Self {
+        NoteRef(note)
+    }
+}
+
+/// Note list body that accepts either a bare array or an object wrapping the array in a
+/// `notes` or `data` field.
+pub(crate) struct NoteListBody(pub(crate) Vec<Note>);
+
+impl<'de> Deserialize<'de> for NoteListBody {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        const EXPECTED: &str = "an array of notes or an object with a `notes` or `data` array";
+
+        let notes = match Value::deserialize(deserializer)? {
+            notes @ Value::Array(_) => notes,
+            Value::Object(mut envelope) => {
+                match envelope.remove("notes").or_else(|| envelope.remove("data")) {
+                    Some(notes @ Value::Array(_)) => notes,
+                    _ => return Err(de::Error::custom(format!("expected {EXPECTED}"))),
+                }
+            }
+            other => {
+                return Err(de::Error::custom(format!(
+                    "expected {EXPECTED}, got {other}"
+                )))
+            }
+        };
+
+        Vec::<Note>::deserialize(notes)
+            .map(NoteListBody)
+            .map_err(de::Error::custom)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SingleNote {
     pub content: String,
@@ -261,7 +719,213 @@ pub struct SingleNote {
     pub note: Note,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Default)]
+fn split_frontmatter(content: &str) -> Option<(&str, &str)> {
+    let rest = content.strip_prefix("---")?;
+    let rest = rest
+        .strip_prefix("\r\n")
+        .or_else(|| rest.strip_prefix('\n'))?;
+
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        if line.trim_end_matches(['\r', '\n']) == "---" {
+            return Some((&rest[..offset], &rest[offset + line.len()..]));
+        }
+        offset += line.len();
+    }
+
+    None
+}
+
+impl SingleNote {
+    pub fn body_without_frontmatter(&self) -> &str {
+        split_frontmatter(&self.content).map_or(self.content.as_str(), |(_, body)| body)
+    }
+
+    #[cfg(feature = "frontmatter")]
+    pub fn frontmatter(&self) -> Option<serde_yaml::Value> {
+        let (frontmatter, _) = split_frontmatter(&self.content)?;
+        serde_yaml::from_str(frontmatter).ok()
+    }
+
+    pub fn edit_url(&self) -> String {
+        format!("{}/{}?edit", self.site_origin(), self.note.id)
+    }
+
+    pub fn view_url(&self) -> String {
+        format!("{}/s/{}", self.site_origin(), self.note.short_id)
+    }
+
+    pub fn slide_url(&self) -> String {
+        format!("{}/p/{}", self.site_origin(), self.note.short_id)
+    }
+
+    pub fn book_url(&self) -> String {
+        format!("{}/c/{}", self.site_origin(), self.note.short_id)
+    }
+
+    /// Scheme and host of `publish_link`, so self-hosted deployments get their own URLs.
+    fn site_origin(&self) -> String {
+        url::Url::parse(&self.note.publish_link)
+            .ok()
+            .filter(|url| url.has_host())
+            .map(|url| url.origin().ascii_serialization())
+            .unwrap_or_else(|| DEFAULT_SITE_ORIGIN.to_string())
+    }
+
+    pub fn to_create_options(&self) -> CreateNoteOptions {
+        CreateNoteOptions {
+            title: Some(self.note.title.clone()),
+            content: Some(self.content.clone()),
+            description: Some(self.note.description.clone()).filter(|d| !d.is_empty()),
+            tags: Some(self.note.tags.clone()),
+            read_permission: Some(self.note.read_permission.clone()),
+            write_permission: Some(self.note.write_permission.clone()),
+            ..Default::default()
+        }
+    }
+}
+
+/// Keeps the note's permalink, for migrating it to another account or instance. To copy a
+/// note within the same workspace, where the permalink is already taken, use
+/// `SingleNote::to_create_options`.
+impl From<&SingleNote> for CreateNoteOptions {
+    fn from(note: &SingleNote) -> Self {
+        CreateNoteOptions {
+            permalink: note.note.permalink.clone(),
+            ..note.to_create_options()
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NoteList {
+    notes: Vec<Note>,
+    page_size: usize,
+    offset: usize,
+}
+
+impl NoteList {
+    pub fn new(notes: Vec<Note>, page_size: usize) -> Self {
+        Self {
+            notes,
+            page_size: page_size.max(1),
+            offset: 0,
+        }
+    }
+
+    pub fn page_size(&self) -> usize {
+        self.page_size
+    }
+
+    pub fn total(&self) -> usize {
+        self.notes.len()
+    }
+
+    pub fn has_next_page(&self) -> bool {
+        self.offset < self.notes.len()
+    }
+
+    pub fn next_page(&mut self) -> Option<&[Note]> {
+        if !self.has_next_page() {
+            return None;
+        }
+
+        let start = self.offset;
+        let end = (start + self.page_size).min(self.notes.len());
+        self.offset = end;
+        Some(&self.notes[start..end])
+    }
+}
+
+impl Iterator for NoteList {
+    type Item = Vec<Note>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_page().map(<[Note]>::to_vec)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct NoteDiff {
+    pub content: bool,
+    pub title: bool,
+    pub tags: bool,
+    pub permissions: bool,
+}
+
+impl NoteDiff {
+    pub fn has_changes(&self) -> bool {
+        self.content || self.title || self.tags || self.permissions
+    }
+}
+
+pub fn diff_notes(a: &SingleNote, b: &SingleNote) -> NoteDiff {
+    NoteDiff {
+        content: a.content != b.content,
+        title: a.note.title != b.note.title,
+        tags: a.note.tags != b.note.tags,
+        permissions: a.note.read_permission != b.note.read_permission
+            || a.note.write_permission != b.note.write_permission,
+    }
+}
+
+fn rewrite_note_url(target: &str, mapping: &HashMap<String, String>) -> Option<String> {
+    let url = url::Url::parse(target).ok()?;
+    let host = url.host_str()?;
+    if host != "hackmd.io" && !host.ends_with(".hackmd.io") {
+        return None;
+    }
+
+    let note_key = url.path_segments()?.rfind(|segment| !segment.is_empty())?;
+    let replacement = mapping.get(note_key)?;
+    Some(match url.fragment() {
+        Some(fragment) => format!("{replacement}#{fragment}"),
+        None => replacement.clone(),
+    })
+}
+
+/// Rewrites markdown links that point at HackMD notes, such as `https://hackmd.io/s/<short_id>`,
+/// to the value `mapping` holds for the note's short id or permalink. Other links are kept as-is.
+pub fn rewrite_note_links(content: &str, mapping: &HashMap<String, String>) -> String {
+    let mut rewritten = String::with_capacity(content.len());
+    let mut rest = content;
+
+    while let Some(start) = rest.find("](") {
+        let target_start = start + 2;
+        let Some(target_len) = rest[target_start..].find([')', ' ', '\n']) else {
+            break;
+        };
+        let target = &rest[target_start..target_start + target_len];
+
+        rewritten.push_str(&rest[..target_start]);
+        match rewrite_note_url(target, mapping) {
+            Some(replacement) => rewritten.push_str(&replacement),
+            None => rewritten.push_str(target),
+        }
+        rest = &rest[target_start + target_len..];
+    }
+
+    rewritten.push_str(rest);
+    rewritten
+}
+
+/// Splits notes into personal notes and team notes grouped by `team_path`, keeping the
+/// input order within each group.
+pub fn partition_notes(notes: Vec<Note>) -> (Vec<Note>, HashMap<String, Vec<Note>>) {
+    let mut personal = Vec::new();
+    let mut by_team: HashMap<String, Vec<Note>> = HashMap::new();
+
+    for note in notes {
+        match note.team_path.clone() {
+            Some(team_path) => by_team.entry(team_path).or_default().push(note),
+            None => personal.push(note),
+        }
+    }
+
+    (personal, by_team)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct UpdateNoteOptions {
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -281,3 +945,16 @@ pub struct UpdateNoteOptions {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub parent_folder_id: Option<String>,
 }
+
+impl UpdateNoteOptions {
+    pub fn to_canonical_json(&self) -> serde_json::Result<String> {
+        to_canonical_json(self)
+    }
+
+    /// The JSON body `ApiClient` sends for these options, with unset fields omitted. Only
+    /// the `normalize_line_endings` client option may still adjust it, by rewriting CRLF
+    /// line endings in `content`.
+    pub fn to_request_json(&self) -> Result<String, ApiError> {
+        Ok(serde_json::to_string(self)?)
+    }
+}
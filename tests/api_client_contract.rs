@@ -1,7 +1,17 @@
-use hackmd_api_client_rs::{ApiClient, ApiError, CreateNoteOptions, UpdateNoteOptions};
+use hackmd_api_client_rs::{
+    ApiClient, ApiClientOptions, ApiError, CreateNoteOptions, NoteContentType, NotePermissionRole,
+    NotePublishType, RateLimit, RateLimitStatus, ResponseMeta, RetryOptions, TeamVisibilityGuard,
+    UpdateNoteOptions,
+};
 use serde_json::json;
-use std::collections::BTreeMap;
-use wiremock::matchers::{body_json, header, method, path};
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+    time,
+};
+use wiremock::matchers::{
+    body_json, body_string, body_string_contains, header, header_regex, method, path, query_param,
+};
 use wiremock::{Mock, MockServer, ResponseTemplate};
 
 fn sample_single_note_response(
@@ -33,6 +43,44 @@ fn sample_single_note_response(
     })
 }
 
+fn sample_user_response() -> serde_json::Value {
+    json!({
+        "id": "user-123",
+        "email": null,
+        "name": "Demo User",
+        "userPath": "demo-user",
+        "photo": "https://hackmd.io/photo.png",
+        "teams": [],
+        "upgraded": false,
+    })
+}
+
+fn sample_team_response(team_path: &str) -> serde_json::Value {
+    json!({
+        "id": format!("team-{team_path}"),
+        "ownerId": "user-123",
+        "name": team_path,
+        "logo": "https://hackmd.io/logo.png",
+        "path": team_path,
+        "description": null,
+        "visibility": "private",
+        "createdAt": 1_710_000_000_000i64,
+        "upgraded": false,
+    })
+}
+
+fn fast_retry_client(server: &MockServer, max_retries: u32) -> ApiClient {
+    let options = ApiClientOptions {
+        retry_options: Some(RetryOptions {
+            max_retries,
+            base_delay: time::Duration::from_millis(1),
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    ApiClient::with_options("test-token", Some(&server.uri()), Some(options)).unwrap()
+}
+
 #[tokio::test]
 async fn get_team_note_uses_team_note_endpoint() {
     let server = MockServer::start().await;
@@ -208,3 +256,2254 @@ async fn error_response_keeps_body_detail() {
         error => panic!("expected HTTP response error, got {error:?}"),
     }
 }
+
+#[tokio::test]
+async fn forbidden_response_maps_to_forbidden_error() {
+    let server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(path("/teams/platform-team/notes"))
+        .and(header("authorization", "Bearer test-token"))
+        .respond_with(ResponseTemplate::new(403).set_body_string("not a team member"))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let client = ApiClient::with_base_url("test-token", &server.uri()).unwrap();
+    let error = client.get_team_notes("platform-team").await.unwrap_err();
+
+    match error {
+        ApiError::Forbidden(error) => {
+            assert_eq!(error.code, 403);
+            assert!(error.message.contains("not a team member"));
+        }
+        error => panic!("expected forbidden error, got {error:?}"),
+    }
+}
+
+#[tokio::test]
+async fn team_client_hits_team_note_endpoints() {
+    let server = MockServer::start().await;
+    let team_note = sample_single_note_response(Some("platform-team"), "Team Note", "# Team");
+
+    Mock::given(method("GET"))
+        .and(path("/teams/platform-team/notes"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([team_note.clone()])))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/teams/platform-team/notes/note-123"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(team_note.clone()))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/teams/platform-team/notes"))
+        .and(body_json(json!({ "title": "Team Note" })))
+        .respond_with(ResponseTemplate::new(201).set_body_json(team_note))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("PATCH"))
+        .and(path("/teams/platform-team/notes/note-123"))
+        .and(body_json(json!({ "content": "# Updated" })))
+        .respond_with(ResponseTemplate::new(202))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("DELETE"))
+        .and(path("/teams/platform-team/notes/note-123"))
+        .respond_with(ResponseTemplate::new(204))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let client = ApiClient::with_base_url("test-token", &server.uri()).unwrap();
+    let team = client.team("platform-team");
+
+    assert_eq!(team.notes().await.unwrap().len(), 1);
+    assert_eq!(team.get_note("note-123").await.unwrap().content, "# Team");
+    team.create_note(&CreateNoteOptions {
+        title: Some("Team Note".to_string()),
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+    team.update_note(
+        "note-123",
+        &UpdateNoteOptions {
+            content: Some("# Updated".to_string()),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    team.delete_note("note-123").await.unwrap();
+}
+
+#[tokio::test]
+async fn patch_note_raw_sends_arbitrary_json_body() {
+    let server = MockServer::start().await;
+    let body = json!({
+        "title": "Raw Patch",
+        "futureField": { "enabled": true }
+    });
+
+    Mock::given(method("PATCH"))
+        .and(path("/notes/note-123"))
+        .and(header("authorization", "Bearer test-token"))
+        .and(body_json(body.clone()))
+        .respond_with(ResponseTemplate::new(202).set_body_json(json!({})))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/notes/note-123"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(sample_single_note_response(
+                None,
+                "Raw Patch",
+                "# Raw",
+            )),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let client = ApiClient::with_base_url("test-token", &server.uri()).unwrap();
+    let note = client.patch_note_raw("note-123", &body).await.unwrap();
+
+    assert_eq!(note.note.title, "Raw Patch");
+    assert_eq!(note.content, "# Raw");
+}
+
+#[tokio::test]
+async fn get_note_by_short_id_resolves_full_id_via_note_list() {
+    let server = MockServer::start().await;
+    let mut listed_note = sample_single_note_response(None, "Listed Note", "# Listed");
+    listed_note.as_object_mut().unwrap().remove("content");
+
+    Mock::given(method("GET"))
+        .and(path("/notes"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([listed_note])))
+        .expect(2)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/notes/note-123"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(sample_single_note_response(
+                None,
+                "Listed Note",
+                "# Listed",
+            )),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let client = ApiClient::with_base_url("test-token", &server.uri()).unwrap();
+    let note = client.get_note_by_short_id("short-123").await.unwrap();
+    assert_eq!(note.note.id, "note-123");
+    assert_eq!(note.content, "# Listed");
+
+    let error = client.get_note_by_short_id("missing").await.unwrap_err();
+    assert!(matches!(error, ApiError::HackMD(_)));
+}
+
+#[tokio::test]
+async fn custom_retry_predicate_overrides_default_classification() {
+    let server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(path("/notes/note-123"))
+        .respond_with(ResponseTemplate::new(404).set_body_string("not yet replicated"))
+        .expect(3)
+        .mount(&server)
+        .await;
+
+    let options = ApiClientOptions {
+        retry_options: Some(RetryOptions {
+            max_retries: 2,
+            base_delay: time::Duration::from_millis(1),
+            retry_predicate: Some(Arc::new(
+                |error| matches!(error, ApiError::HttpResponse(error) if error.code == 404),
+            )),
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    let client = ApiClient::with_options("test-token", Some(&server.uri()), Some(options)).unwrap();
+    let error = client.get_note("note-123").await.unwrap_err();
+
+    assert!(matches!(error, ApiError::HttpResponse(error) if error.code == 404));
+}
+
+#[tokio::test]
+async fn batch_updater_coalesces_rapid_updates_into_one_patch() {
+    let server = MockServer::start().await;
+
+    Mock::given(method("PATCH"))
+        .and(path("/notes/note-123"))
+        .and(body_json(json!({ "content": "draft 5" })))
+        .respond_with(ResponseTemplate::new(202))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let client = ApiClient::with_base_url("test-token", &server.uri()).unwrap();
+    let batch = client.batch_updater(time::Duration::from_secs(60)).unwrap();
+
+    for draft in 1..=5 {
+        batch
+            .update_note_content("note-123", &format!("draft {draft}"))
+            .unwrap();
+    }
+    assert!(batch.flush().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn batch_updater_flushes_when_debounce_window_elapses() {
+    let server = MockServer::start().await;
+
+    Mock::given(method("PATCH"))
+        .and(path("/notes/note-123"))
+        .and(body_json(json!({ "content": "draft 3" })))
+        .respond_with(ResponseTemplate::new(202))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let client = ApiClient::with_base_url("test-token", &server.uri()).unwrap();
+    let batch = client
+        .batch_updater(time::Duration::from_millis(50))
+        .unwrap();
+
+    for draft in 1..=3 {
+        batch
+            .update_note_content("note-123", &format!("draft {draft}"))
+            .unwrap();
+    }
+    tokio::time::sleep(time::Duration::from_millis(300)).await;
+
+    assert_eq!(server.received_requests().await.unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn get_me_with_stats_counts_retried_attempts() {
+    let server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(path("/me"))
+        .respond_with(ResponseTemplate::new(503))
+        .up_to_n_times(2)
+        .expect(2)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/me"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(sample_user_response()))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let client = fast_retry_client(&server, 3);
+    let (user, stats) = client.get_me_with_stats().await.unwrap();
+
+    assert_eq!(user.user_path, "demo-user");
+    assert_eq!(stats.attempts, 3);
+    assert_eq!(stats.total_wait, time::Duration::from_millis(3));
+}
+
+#[tokio::test]
+async fn rate_limit_without_headers_is_still_retried() {
+    let server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(path("/me"))
+        .respond_with(ResponseTemplate::new(429))
+        .up_to_n_times(1)
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/me"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(sample_user_response()))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let client = fast_retry_client(&server, 3);
+    let (_, stats) = client.get_me_with_stats().await.unwrap();
+
+    assert_eq!(stats.attempts, 2);
+}
+
+#[tokio::test]
+async fn create_note_content_with_markdown_type_sends_raw_markdown() {
+    let server = MockServer::start().await;
+
+    Mock::given(method("POST"))
+        .and(path("/notes"))
+        .and(header("content-type", "text/markdown"))
+        .and(body_string("# Raw markdown"))
+        .respond_with(
+            ResponseTemplate::new(201).set_body_json(sample_single_note_response(
+                None,
+                "Raw Markdown Note",
+                "# Raw markdown",
+            )),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("PATCH"))
+        .and(path("/notes/note-123"))
+        .and(header("content-type", "text/html"))
+        .and(body_string("<h1>Raw HTML</h1>"))
+        .respond_with(ResponseTemplate::new(202))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let client = ApiClient::with_base_url("test-token", &server.uri()).unwrap();
+    let note = client
+        .create_note_content_with_type("# Raw markdown", NoteContentType::Markdown)
+        .await
+        .unwrap();
+    assert_eq!(note.content, "# Raw markdown");
+
+    client
+        .update_note_content_with_type("note-123", "<h1>Raw HTML</h1>", NoteContentType::Html)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn get_team_notes_by_author_filters_on_last_change_user() {
+    let server = MockServer::start().await;
+    let editor = |user_path: &str| {
+        json!({
+            "name": user_path,
+            "userPath": user_path,
+            "photo": "https://hackmd.io/photo.png",
+            "biography": null,
+        })
+    };
+    let mut by_author = sample_single_note_response(Some("platform-team"), "Mine", "");
+    by_author["id"] = json!("note-mine");
+    by_author["lastChangeUser"] = editor("demo-user");
+    let mut by_other = sample_single_note_response(Some("platform-team"), "Theirs", "");
+    by_other["id"] = json!("note-theirs");
+    by_other["lastChangeUser"] = editor("other-user");
+    let without_editor = sample_single_note_response(Some("platform-team"), "Nobody", "");
+
+    Mock::given(method("GET"))
+        .and(path("/teams/platform-team/notes"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([
+            by_author,
+            by_other,
+            without_editor
+        ])))
+        .mount(&server)
+        .await;
+
+    let client = ApiClient::with_base_url("test-token", &server.uri()).unwrap();
+    let notes = client
+        .get_team_notes_by_author("platform-team", "demo-user")
+        .await
+        .unwrap();
+
+    assert_eq!(notes.len(), 1);
+    assert_eq!(notes[0].id, "note-mine");
+}
+
+#[tokio::test]
+async fn reqwest_timeout_display_names_the_error_kind() {
+    let server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(path("/me"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(sample_user_response())
+                .set_delay(time::Duration::from_millis(500)),
+        )
+        .mount(&server)
+        .await;
+
+    let options = ApiClientOptions {
+        timeout: Some(time::Duration::from_millis(50)),
+        retry_options: None,
+        ..Default::default()
+    };
+    let client = ApiClient::with_options("test-token", Some(&server.uri()), Some(options)).unwrap();
+    let error = client.get_me().await.unwrap_err();
+
+    assert!(matches!(error, ApiError::Reqwest(_)));
+    assert!(error.to_string().starts_with("Request error (timeout):"));
+}
+
+#[tokio::test]
+async fn response_body_over_limit_is_rejected() {
+    let server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(path("/me"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(sample_user_response()))
+        .mount(&server)
+        .await;
+
+    let options = ApiClientOptions {
+        max_response_bytes: Some(16),
+        ..Default::default()
+    };
+    let client = ApiClient::with_options("test-token", Some(&server.uri()), Some(options)).unwrap();
+    let error = client.get_me().await.unwrap_err();
+
+    match error {
+        ApiError::Validation(error) => assert!(error.message.contains("16 byte limit")),
+        error => panic!("expected validation error, got {error:?}"),
+    }
+}
+
+#[tokio::test]
+async fn move_note_to_team_fetches_creates_then_deletes() {
+    let server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(path("/notes/note-123"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(sample_single_note_response(
+                None,
+                "Personal Note",
+                "# Personal",
+            )),
+        )
+        .expect(2)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/teams/platform-team/notes"))
+        .and(body_json(json!({
+            "title": "Personal Note",
+            "content": "# Personal",
+            "tags": [],
+            "readPermission": "owner",
+            "writePermission": "owner"
+        })))
+        .respond_with(
+            ResponseTemplate::new(201).set_body_json(sample_single_note_response(
+                Some("platform-team"),
+                "Personal Note",
+                "# Personal",
+            )),
+        )
+        .expect(2)
+        .mount(&server)
+        .await;
+    Mock::given(method("DELETE"))
+        .and(path("/notes/note-123"))
+        .respond_with(ResponseTemplate::new(204))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let client = ApiClient::with_base_url("test-token", &server.uri()).unwrap();
+    let moved = client
+        .move_note_to_team("note-123", "platform-team", true)
+        .await
+        .unwrap();
+    assert_eq!(moved.note.team_path.as_deref(), Some("platform-team"));
+    assert_eq!(moved.content, "# Personal");
+
+    client
+        .move_note_to_team("note-123", "platform-team", false)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn clone_note_copies_source_content_with_new_title() {
+    let server = MockServer::start().await;
+    let mut source = sample_single_note_response(None, "Template", "# Template body");
+    source["tags"] = json!(["template"]);
+    source["permalink"] = json!("template");
+
+    Mock::given(method("GET"))
+        .and(path("/notes/note-123"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(source))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/notes"))
+        .and(body_json(json!({
+            "title": "Copy of Template",
+            "content": "# Template body",
+            "tags": ["template"],
+            "readPermission": "owner",
+            "writePermission": "owner"
+        })))
+        .respond_with(
+            ResponseTemplate::new(201).set_body_json(sample_single_note_response(
+                None,
+                "Copy of Template",
+                "# Template body",
+            )),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let client = ApiClient::with_base_url("test-token", &server.uri()).unwrap();
+    let copy = client
+        .clone_note("note-123", Some("Copy of Template"))
+        .await
+        .unwrap();
+
+    assert_eq!(copy.note.title, "Copy of Template");
+    assert_eq!(copy.content, "# Template body");
+}
+
+#[tokio::test]
+async fn normalize_line_endings_converts_crlf_only_when_enabled() {
+    let server = MockServer::start().await;
+
+    Mock::given(method("POST"))
+        .and(path("/notes"))
+        .and(body_json(json!({ "content": "# Title\nBody\n" })))
+        .respond_with(
+            ResponseTemplate::new(201).set_body_json(sample_single_note_response(
+                None,
+                "Title",
+                "# Title\nBody\n",
+            )),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/notes"))
+        .and(body_json(json!({ "content": "# Title\r\nBody\r\n" })))
+        .respond_with(
+            ResponseTemplate::new(201).set_body_json(sample_single_note_response(
+                None,
+                "Title",
+                "# Title\r\nBody\r\n",
+            )),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let payload = CreateNoteOptions {
+        content: Some("# Title\r\nBody\r\n".to_string()),
+        ..Default::default()
+    };
+    let normalizing_client = ApiClient::with_options(
+        "test-token",
+        Some(&server.uri()),
+        Some(ApiClientOptions {
+            normalize_line_endings: true,
+            ..Default::default()
+        }),
+    )
+    .unwrap();
+    normalizing_client.create_note(&payload).await.unwrap();
+
+    let default_client = ApiClient::with_base_url("test-token", &server.uri()).unwrap();
+    default_client.create_note(&payload).await.unwrap();
+}
+
+async fn mount_truncated_then_valid_me(server: &MockServer) {
+    Mock::given(method("GET"))
+        .and(path("/me"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(
+            r#"{"id":"user-123","name":"#.as_bytes().to_vec(),
+            "application/json",
+        ))
+        .up_to_n_times(1)
+        .mount(server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/me"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(sample_user_response()))
+        .mount(server)
+        .await;
+}
+
+#[tokio::test]
+async fn decode_errors_are_retried_only_when_enabled() {
+    let server = MockServer::start().await;
+    mount_truncated_then_valid_me(&server).await;
+
+    let client = fast_retry_client(&server, 3);
+    let error = client.get_me().await.unwrap_err();
+    assert!(matches!(error, ApiError::Reqwest(ref err) if err.is_decode()));
+
+    let server = MockServer::start().await;
+    mount_truncated_then_valid_me(&server).await;
+
+    let options = ApiClientOptions {
+        retry_options: Some(RetryOptions {
+            max_retries: 3,
+            base_delay: time::Duration::from_millis(1),
+            retry_decode_errors: true,
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    let client = ApiClient::with_options("test-token", Some(&server.uri()), Some(options)).unwrap();
+    let (user, stats) = client.get_me_with_stats().await.unwrap();
+
+    assert_eq!(user.user_path, "demo-user");
+    assert_eq!(stats.attempts, 2);
+}
+
+#[tokio::test]
+async fn single_flight_shares_one_request_between_concurrent_gets() {
+    let server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(path("/me"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(sample_user_response())
+                .set_delay(time::Duration::from_millis(200)),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let options = ApiClientOptions {
+        single_flight: true,
+        ..Default::default()
+    };
+    let client = ApiClient::with_options("test-token", Some(&server.uri()), Some(options)).unwrap();
+    let (first, second, third, fourth) = tokio::join!(
+        client.get_me(),
+        client.get_me(),
+        client.get_me(),
+        client.get_me()
+    );
+
+    for user in [first, second, third, fourth] {
+        assert_eq!(user.unwrap().user_path, "demo-user");
+    }
+}
+
+#[tokio::test]
+async fn get_all_team_notes_reports_failed_teams() {
+    let server = MockServer::start().await;
+    let mut user = sample_user_response();
+    user["teams"] = json!([
+        sample_team_response("platform-team"),
+        sample_team_response("broken-team"),
+        sample_team_response("design-team"),
+    ]);
+
+    Mock::given(method("GET"))
+        .and(path("/me"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(user))
+        .mount(&server)
+        .await;
+    for team_path in ["platform-team", "design-team"] {
+        Mock::given(method("GET"))
+            .and(path(format!("/teams/{team_path}/notes")))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!([
+                sample_single_note_response(Some(team_path), "Team Note", "")
+            ])))
+            .expect(1)
+            .mount(&server)
+            .await;
+    }
+    Mock::given(method("GET"))
+        .and(path("/teams/broken-team/notes"))
+        .respond_with(ResponseTemplate::new(500))
+        .mount(&server)
+        .await;
+
+    let client = ApiClient::with_base_url("test-token", &server.uri()).unwrap();
+    let team_notes = client.get_all_team_notes().await.unwrap();
+    let team_paths: Vec<&str> = team_notes.iter().map(|(path, _)| path.as_str()).collect();
+
+    assert_eq!(team_paths, ["platform-team", "broken-team", "design-team"]);
+    assert_eq!(team_notes[0].1.as_ref().unwrap().len(), 1);
+    assert!(matches!(
+        team_notes[1].1,
+        Err(ApiError::InternalServer(ref error)) if error.code == 500
+    ));
+    assert_eq!(team_notes[2].1.as_ref().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn get_history_paginated_sends_offset_and_limit() {
+    let server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(path("/history"))
+        .and(query_param("offset", "20"))
+        .and(query_param("limit", "10"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(json!([sample_single_note_response(None, "Viewed", "")])),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let client = ApiClient::with_base_url("test-token", &server.uri()).unwrap();
+    let notes = client.get_history_paginated(20, 10).await.unwrap();
+
+    assert_eq!(notes.len(), 1);
+    assert_eq!(notes[0].id, "note-123");
+}
+
+#[tokio::test]
+async fn get_history_paginated_slices_when_server_ignores_parameters() {
+    let server = MockServer::start().await;
+    let history: Vec<serde_json::Value> = (0..5)
+        .map(|index| {
+            let mut note = sample_single_note_response(None, "Viewed", "");
+            note["id"] = json!(format!("note-{index}"));
+            note
+        })
+        .collect();
+
+    Mock::given(method("GET"))
+        .and(path("/history"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(history))
+        .mount(&server)
+        .await;
+
+    let client = ApiClient::with_base_url("test-token", &server.uri()).unwrap();
+    let notes = client.get_history_paginated(1, 2).await.unwrap();
+    let ids: Vec<&str> = notes.iter().map(|note| note.id.as_str()).collect();
+
+    assert_eq!(ids, ["note-1", "note-2"]);
+}
+
+#[tokio::test]
+async fn overall_timeout_caps_the_whole_retry_loop() {
+    let server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(path("/me"))
+        .respond_with(ResponseTemplate::new(500))
+        .mount(&server)
+        .await;
+
+    let options = ApiClientOptions {
+        overall_timeout: Some(time::Duration::from_millis(150)),
+        retry_options: Some(RetryOptions {
+            max_retries: 10,
+            base_delay: time::Duration::from_millis(100),
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    let client = ApiClient::with_options("test-token", Some(&server.uri()), Some(options)).unwrap();
+
+    let started = std::time::Instant::now();
+    let error = client.get_me().await.unwrap_err();
+
+    assert!(started.elapsed() < time::Duration::from_secs(1));
+    match error {
+        ApiError::Timeout(error) => assert_eq!(error.timeout, time::Duration::from_millis(150)),
+        error => panic!("expected timeout error, got {error:?}"),
+    }
+}
+
+#[tokio::test]
+async fn upload_image_sends_multipart_form_and_returns_link() {
+    let server = MockServer::start().await;
+
+    Mock::given(method("POST"))
+        .and(path("/notes/note-123/images"))
+        .and(header_regex(
+            "content-type",
+            "^multipart/form-data; boundary=",
+        ))
+        .and(body_string_contains(
+            r#"name="image"; filename="diagram.png""#,
+        ))
+        .and(body_string_contains("Content-Type: image/png"))
+        .respond_with(ResponseTemplate::new(201).set_body_json(json!({
+            "data": { "link": "https://hackmd.io/_uploads/diagram.png" }
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let client = ApiClient::with_base_url("test-token", &server.uri()).unwrap();
+    let link = client
+        .upload_image(
+            "note-123",
+            b"png-bytes".to_vec(),
+            "diagram.png",
+            "image/png",
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(link, "https://hackmd.io/_uploads/diagram.png");
+}
+
+#[tokio::test]
+async fn set_access_token_is_reflected_by_next_get_me() {
+    let server = MockServer::start().await;
+    let mut other_user = sample_user_response();
+    other_user["id"] = json!("user-456");
+    other_user["name"] = json!("Other User");
+
+    Mock::given(method("GET"))
+        .and(path("/me"))
+        .and(header("authorization", "Bearer token-a"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(sample_user_response()))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/me"))
+        .and(header("authorization", "Bearer token-b"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(other_user))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let mut client = ApiClient::with_base_url("token-a", &server.uri()).unwrap();
+    assert_eq!(client.get_me().await.unwrap().id, "user-123");
+
+    client.set_access_token("token-b").unwrap();
+    let user = client.get_me().await.unwrap();
+
+    assert_eq!(user.id, "user-456");
+    assert_eq!(user.name, "Other User");
+    assert!(matches!(
+        client.set_access_token("  "),
+        Err(ApiError::MissingRequiredArgument(_))
+    ));
+}
+
+#[cfg(feature = "request-id")]
+#[tokio::test]
+async fn request_id_header_is_unique_per_request() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/me"))
+        .and(header_regex(
+            "x-request-id",
+            "^[0-9a-f]{8}-[0-9a-f]{4}-4[0-9a-f]{3}-[0-9a-f]{4}-[0-9a-f]{12}$",
+        ))
+        .respond_with(ResponseTemplate::new(200).set_body_json(sample_user_response()))
+        .expect(2)
+        .mount(&server)
+        .await;
+
+    let client = ApiClient::with_base_url("test-token", &server.uri()).unwrap();
+    assert_eq!(client.last_request_id(), None);
+
+    client.get_me().await.unwrap();
+    let first_id = client.last_request_id().unwrap();
+    client.get_me().await.unwrap();
+    let second_id = client.last_request_id().unwrap();
+
+    let received: Vec<_> = server
+        .received_requests()
+        .await
+        .unwrap()
+        .iter()
+        .map(|request| {
+            request.headers["x-request-id"]
+                .to_str()
+                .unwrap()
+                .to_string()
+        })
+        .collect();
+    assert_eq!(received, vec![first_id.clone(), second_id.clone()]);
+    assert_ne!(first_id, second_id);
+}
+
+#[tokio::test]
+async fn update_notes_reports_per_id_results() {
+    let server = MockServer::start().await;
+    for note_id in ["note-1", "note-3"] {
+        Mock::given(method("PATCH"))
+            .and(path(format!("/notes/{note_id}")))
+            .and(body_json(json!({ "readPermission": "signed_in" })))
+            .respond_with(ResponseTemplate::new(202))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let mut note = sample_single_note_response(None, note_id, "# Updated");
+        note["id"] = json!(note_id);
+        note["readPermission"] = json!("signed_in");
+        Mock::given(method("GET"))
+            .and(path(format!("/notes/{note_id}")))
+            .respond_with(ResponseTemplate::new(200).set_body_json(note))
+            .expect(1)
+            .mount(&server)
+            .await;
+    }
+    Mock::given(method("PATCH"))
+        .and(path("/notes/note-2"))
+        .respond_with(ResponseTemplate::new(404).set_body_string("Note not found"))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let client = ApiClient::with_base_url("test-token", &server.uri()).unwrap();
+    let payload = UpdateNoteOptions {
+        read_permission: Some(NotePermissionRole::SignedIn),
+        ..Default::default()
+    };
+    let results = client
+        .update_notes(&["note-1", "note-2", "note-3"], &payload, 2)
+        .await;
+
+    let ids: Vec<_> = results.iter().map(|(id, _)| id.as_str()).collect();
+    assert_eq!(ids, ["note-1", "note-2", "note-3"]);
+    assert_eq!(results[0].1.as_ref().unwrap().note.id, "note-1");
+    assert!(matches!(
+        results[1].1,
+        Err(ApiError::HttpResponse(ref error)) if error.code == 404
+    ));
+    assert_eq!(results[2].1.as_ref().unwrap().note.id, "note-3");
+}
+
+#[tokio::test]
+async fn create_note_is_not_retried_by_default() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/notes"))
+        .respond_with(ResponseTemplate::new(503))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let client = fast_retry_client(&server, 3);
+    let error = client
+        .create_note(&CreateNoteOptions::default())
+        .await
+        .unwrap_err();
+
+    assert!(matches!(error, ApiError::InternalServer(ref err) if err.code == 503));
+}
+
+#[tokio::test]
+async fn create_note_is_retried_when_non_idempotent_retries_are_enabled() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/notes"))
+        .respond_with(ResponseTemplate::new(503))
+        .up_to_n_times(1)
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/notes"))
+        .respond_with(
+            ResponseTemplate::new(201)
+                .set_body_json(sample_single_note_response(None, "Retried", "")),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let options = ApiClientOptions {
+        retry_options: Some(RetryOptions {
+            base_delay: time::Duration::from_millis(1),
+            retry_non_idempotent: true,
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    let client = ApiClient::with_options("test-token", Some(&server.uri()), Some(options)).unwrap();
+    let note = client
+        .create_note(&CreateNoteOptions::default())
+        .await
+        .unwrap();
+
+    assert_eq!(note.note.title, "Retried");
+}
+
+#[tokio::test]
+async fn create_note_idempotency_key_is_stable_across_retries() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/notes"))
+        .respond_with(ResponseTemplate::new(503))
+        .up_to_n_times(2)
+        .expect(2)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/notes"))
+        .respond_with(
+            ResponseTemplate::new(201)
+                .set_body_json(sample_single_note_response(None, "Created", "")),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let options = ApiClientOptions {
+        retry_options: Some(RetryOptions {
+            base_delay: time::Duration::from_millis(1),
+            retry_non_idempotent: true,
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    let client = ApiClient::with_options("test-token", Some(&server.uri()), Some(options)).unwrap();
+    client
+        .create_note(&CreateNoteOptions::default())
+        .await
+        .unwrap();
+
+    let keys: Vec<_> = server
+        .received_requests()
+        .await
+        .unwrap()
+        .iter()
+        .map(|request| {
+            request.headers["idempotency-key"]
+                .to_str()
+                .unwrap()
+                .to_string()
+        })
+        .collect();
+    assert_eq!(keys.len(), 3);
+    assert!(!keys[0].is_empty());
+    assert!(keys.iter().all(|key| key == &keys[0]));
+}
+
+#[tokio::test]
+async fn create_note_sends_caller_supplied_idempotency_key() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/notes"))
+        .and(header("idempotency-key", "create-weekly-sync"))
+        .and(body_json(json!({ "title": "Weekly sync" })))
+        .respond_with(
+            ResponseTemplate::new(201).set_body_json(sample_single_note_response(
+                None,
+                "Weekly sync",
+                "",
+            )),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let client = ApiClient::with_base_url("test-token", &server.uri()).unwrap();
+    client
+        .create_note(&CreateNoteOptions {
+            title: Some("Weekly sync".to_string()),
+            idempotency_key: Some("create-weekly-sync".to_string()),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn rate_limit_is_shared_across_clones() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/me"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(sample_user_response()))
+        .expect(4)
+        .mount(&server)
+        .await;
+
+    let options = ApiClientOptions {
+        rate_limit: Some(RateLimit {
+            max_requests: 1,
+            per: time::Duration::from_millis(100),
+        }),
+        ..Default::default()
+    };
+    let client = ApiClient::with_options("test-token", Some(&server.uri()), Some(options)).unwrap();
+    let clone = client.clone();
+
+    let started = time::Instant::now();
+    let (first, second, third, fourth) = tokio::join!(
+        client.get_me(),
+        clone.get_me(),
+        client.get_me(),
+        clone.get_me()
+    );
+    let elapsed = started.elapsed();
+
+    for result in [first, second, third, fourth] {
+        result.unwrap();
+    }
+    assert!(
+        elapsed >= time::Duration::from_millis(290),
+        "four requests at one per 100ms finished in {elapsed:?}"
+    );
+}
+
+#[tokio::test]
+async fn get_note_list_accepts_enveloped_response() {
+    let server = MockServer::start().await;
+    let mut note = sample_single_note_response(None, "Enveloped", "");
+    note.as_object_mut().unwrap().remove("content");
+    Mock::given(method("GET"))
+        .and(path("/notes"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "notes": [note] })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let client = ApiClient::with_base_url("test-token", &server.uri()).unwrap();
+    let notes = client.get_note_list().await.unwrap();
+
+    assert_eq!(notes.len(), 1);
+    assert_eq!(notes[0].title, "Enveloped");
+}
+
+#[tokio::test]
+async fn metrics_accumulate_requests_retries_and_error_statuses() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/me"))
+        .respond_with(ResponseTemplate::new(429))
+        .up_to_n_times(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/me"))
+        .respond_with(ResponseTemplate::new(503))
+        .up_to_n_times(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/me"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(sample_user_response()))
+        .mount(&server)
+        .await;
+
+    let client = fast_retry_client(&server, 3);
+    let clone = client.clone();
+    client.get_me().await.unwrap();
+    clone.get_me().await.unwrap();
+
+    let metrics = client.metrics();
+    assert_eq!(metrics.total_requests, 4);
+    assert_eq!(metrics.total_retries, 2);
+    assert_eq!(metrics.total_429s, 1);
+    assert_eq!(metrics.total_5xx, 1);
+    assert_eq!(clone.metrics(), metrics);
+}
+
+#[tokio::test]
+async fn connect_with_verify_on_build_checks_the_token() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/me"))
+        .and(header("authorization", "Bearer valid-token"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(sample_user_response()))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/me"))
+        .and(header("authorization", "Bearer revoked-token"))
+        .respond_with(ResponseTemplate::new(401).set_body_string("Unauthorized"))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let options = || ApiClientOptions {
+        verify_on_build: true,
+        ..Default::default()
+    };
+    let client = ApiClient::connect("valid-token", Some(&server.uri()), Some(options())).await;
+    assert!(client.is_ok());
+
+    let error = ApiClient::connect("revoked-token", Some(&server.uri()), Some(options()))
+        .await
+        .err()
+        .unwrap();
+    assert!(matches!(error, ApiError::HttpResponse(ref err) if err.code == 401));
+}
+
+#[tokio::test]
+async fn update_notes_with_progress_reports_each_completed_note() {
+    let server = MockServer::start().await;
+    Mock::given(method("PATCH"))
+        .respond_with(ResponseTemplate::new(202))
+        .expect(4)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(sample_single_note_response(None, "Note", "")),
+        )
+        .expect(4)
+        .mount(&server)
+        .await;
+
+    let reports = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let recorded = Arc::clone(&reports);
+    let progress: hackmd_api_client_rs::ProgressCallback = Arc::new(move |done, total| {
+        recorded.lock().unwrap().push((done, total));
+    });
+
+    let client = ApiClient::with_base_url("test-token", &server.uri()).unwrap();
+    let results = client
+        .update_notes_with_progress(
+            &["note-1", "note-2", "note-3", "note-4"],
+            &UpdateNoteOptions::default(),
+            2,
+            Some(progress),
+        )
+        .await;
+
+    assert!(results.iter().all(|(_, result)| result.is_ok()));
+    assert_eq!(*reports.lock().unwrap(), [(1, 4), (2, 4), (3, 4), (4, 4)]);
+}
+
+#[tokio::test]
+async fn create_note_permalink_conflict_suggests_alternative() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/notes"))
+        .and(body_json(json!({ "permalink": "weekly-sync" })))
+        .respond_with(ResponseTemplate::new(409).set_body_string("Permalink already exists"))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let client = ApiClient::with_base_url("test-token", &server.uri()).unwrap();
+    let error = client
+        .create_note(&CreateNoteOptions {
+            permalink: Some("weekly-sync".to_string()),
+            ..Default::default()
+        })
+        .await
+        .unwrap_err();
+
+    match error {
+        ApiError::Conflict(err) => {
+            assert_eq!(err.code, 409);
+            assert_eq!(err.suggested_permalink.as_deref(), Some("weekly-sync-2"));
+        }
+        other => panic!("expected Conflict, got {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn append_and_prepend_patch_concatenated_content() {
+    let server = MockServer::start().await;
+    let note = |content: &str, last_changed_at: i64| {
+        let mut note = sample_single_note_response(None, "Changelog", content);
+        note["lastChangedAt"] = json!(last_changed_at);
+        note
+    };
+    // Each helper reads the note, writes it, then re-reads the written state.
+    for (content, last_changed_at) in [
+        ("# Changelog\n", 1_710_000_000_000i64),
+        ("# Changelog\n- v1.1\n", 1_710_000_001_000),
+        ("# Changelog\n- v1.1\n", 1_710_000_001_000),
+        ("---\n# Changelog\n- v1.1\n", 1_710_000_002_000),
+    ] {
+        Mock::given(method("GET"))
+            .and(path("/notes/note-123"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(note(content, last_changed_at)))
+            .up_to_n_times(1)
+            .expect(1)
+            .mount(&server)
+            .await;
+    }
+    Mock::given(method("PATCH"))
+        .and(path("/notes/note-123"))
+        .and(body_json(json!({ "content": "# Changelog\n- v1.1\n" })))
+        .respond_with(ResponseTemplate::new(202))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("PATCH"))
+        .and(path("/notes/note-123"))
+        .and(body_json(
+            json!({ "content": "---\n# Changelog\n- v1.1\n" }),
+        ))
+        .respond_with(ResponseTemplate::new(202))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let client = ApiClient::with_base_url("test-token", &server.uri()).unwrap();
+    let appended = client.append_to_note("note-123", "- v1.1\n").await.unwrap();
+    assert_eq!(appended.content, "# Changelog\n- v1.1\n");
+    assert_eq!(
+        appended.note.last_changed_at.timestamp_millis(),
+        1_710_000_001_000
+    );
+
+    let prepended = client.prepend_to_note("note-123", "---\n").await.unwrap();
+    assert_eq!(prepended.content, "---\n# Changelog\n- v1.1\n");
+    assert_eq!(
+        prepended.note.last_changed_at.timestamp_millis(),
+        1_710_000_002_000
+    );
+}
+
+#[tokio::test]
+async fn get_or_create_by_title_returns_most_recent_match() {
+    let server = MockServer::start().await;
+    let note = |id: &str, title: &str, last_changed_at: i64| {
+        let mut note = sample_single_note_response(None, title, "");
+        note.as_object_mut().unwrap().remove("content");
+        note["id"] = json!(id);
+        note["lastChangedAt"] = json!(last_changed_at);
+        note
+    };
+    Mock::given(method("GET"))
+        .and(path("/notes"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([
+            note("note-old", "Inbox", 1_700_000_000_000i64),
+            note("note-new", "Inbox", 1_710_000_000_000i64),
+            note("note-other", "Journal", 1_720_000_000_000i64),
+        ])))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let mut found = sample_single_note_response(None, "Inbox", "# Inbox");
+    found["id"] = json!("note-new");
+    Mock::given(method("GET"))
+        .and(path("/notes/note-new"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(found))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/notes"))
+        .respond_with(ResponseTemplate::new(201))
+        .expect(0)
+        .mount(&server)
+        .await;
+
+    let client = ApiClient::with_base_url("test-token", &server.uri()).unwrap();
+    let note = client
+        .get_or_create_by_title("Inbox", &CreateNoteOptions::default())
+        .await
+        .unwrap();
+
+    assert_eq!(note.note.id, "note-new");
+    assert_eq!(note.content, "# Inbox");
+}
+
+#[tokio::test]
+async fn get_or_create_by_title_creates_missing_note() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/notes"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([])))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/notes"))
+        .and(body_json(json!({ "title": "Inbox", "content": "# Inbox" })))
+        .respond_with(
+            ResponseTemplate::new(201)
+                .set_body_json(sample_single_note_response(None, "Inbox", "# Inbox")),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let client = ApiClient::with_base_url("test-token", &server.uri()).unwrap();
+    let default = CreateNoteOptions {
+        title: Some("ignored".to_string()),
+        content: Some("# Inbox".to_string()),
+        ..Default::default()
+    };
+    let note = client
+        .get_or_create_by_title("Inbox", &default)
+        .await
+        .unwrap();
+
+    assert_eq!(note.note.title, "Inbox");
+}
+
+#[cfg(feature = "tracing")]
+#[tokio::test]
+#[tracing_test::traced_test]
+async fn create_note_logs_request_payload_size() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/notes"))
+        .respond_with(
+            ResponseTemplate::new(201)
+                .set_body_json(sample_single_note_response(None, "Sized", "")),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let client = ApiClient::with_base_url("test-token", &server.uri()).unwrap();
+    client
+        .create_note(&CreateNoteOptions {
+            title: Some("Sized".to_string()),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    let expected_bytes = r#"{"title":"Sized"}"#.len();
+    assert!(logs_contain("operation=\"create_note\""));
+    assert!(logs_contain(&format!("payload_bytes={expected_bytes}")));
+}
+
+#[cfg(feature = "stream")]
+#[tokio::test]
+async fn watch_notes_yields_changes_and_survives_errors() {
+    use futures_util::StreamExt;
+
+    let server = MockServer::start().await;
+    let note = |id: &str, last_changed_at: i64| {
+        let mut note = sample_single_note_response(None, id, "");
+        note.as_object_mut().unwrap().remove("content");
+        note["id"] = json!(id);
+        note["lastChangedAt"] = json!(last_changed_at);
+        note
+    };
+    Mock::given(method("GET"))
+        .and(path("/history"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(json!([note("note-a", 1_700_000_000_000i64)])),
+        )
+        .up_to_n_times(2)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/history"))
+        .respond_with(ResponseTemplate::new(404))
+        .up_to_n_times(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/history"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([
+            note("note-b", 1_710_000_000_000i64),
+            note("note-a", 1_700_000_000_000i64),
+        ])))
+        .mount(&server)
+        .await;
+
+    let client = ApiClient::with_base_url("test-token", &server.uri()).unwrap();
+    let mut updates = std::pin::pin!(client.watch_notes(time::Duration::from_millis(1)));
+
+    let first = updates.next().await.unwrap().unwrap();
+    assert_eq!(first.len(), 1);
+    assert_eq!(first[0].id, "note-a");
+
+    assert!(updates.next().await.unwrap().is_err());
+
+    let changed = updates.next().await.unwrap().unwrap();
+    assert_eq!(changed.len(), 1);
+    assert_eq!(changed[0].id, "note-b");
+}
+
+#[tokio::test]
+async fn reject_binary_content_blocks_upload_before_sending() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/notes"))
+        .respond_with(ResponseTemplate::new(201))
+        .expect(0)
+        .mount(&server)
+        .await;
+    Mock::given(method("PATCH"))
+        .and(path("/notes/note-123"))
+        .respond_with(ResponseTemplate::new(202))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let client = ApiClient::with_options(
+        "test_token",
+        Some(&server.uri()),
+        Some(ApiClientOptions {
+            reject_binary_content: true,
+            ..Default::default()
+        }),
+    )
+    .unwrap();
+
+    let error = client
+        .create_note(&CreateNoteOptions {
+            content: Some("\u{0}\u{0}\u{1}ELF".to_string()),
+            ..Default::default()
+        })
+        .await
+        .unwrap_err();
+    assert!(matches!(error, ApiError::Validation(_)));
+
+    client
+        .update_note_content("note-123", "# Clean\n\nmarkdown")
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn retried_patch_replays_identical_body() {
+    let server = MockServer::start().await;
+    Mock::given(method("PATCH"))
+        .and(path("/notes/note-123"))
+        .respond_with(ResponseTemplate::new(503))
+        .up_to_n_times(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("PATCH"))
+        .and(path("/notes/note-123"))
+        .respond_with(ResponseTemplate::new(202))
+        .mount(&server)
+        .await;
+    let client = fast_retry_client(&server, 2);
+
+    client
+        .update_note(
+            "note-123",
+            &UpdateNoteOptions {
+                content: Some("# Retried\n\nbody".to_string()),
+                read_permission: Some(NotePermissionRole::Guest),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+
+    let received = server.received_requests().await.unwrap();
+    assert_eq!(received.len(), 2);
+    assert_eq!(received[0].body, received[1].body);
+    assert_eq!(
+        received[1].headers["content-type"].to_str().unwrap(),
+        "application/json"
+    );
+    let body: serde_json::Value = serde_json::from_slice(&received[1].body).unwrap();
+    assert_eq!(body["content"], "# Retried\n\nbody");
+}
+
+#[tokio::test]
+async fn team_visibility_guard_rejects_public_note_in_private_team() {
+    let server = MockServer::start().await;
+    let mut me = sample_user_response();
+    me["teams"] = json!([sample_team_response("platform")]);
+    Mock::given(method("GET"))
+        .and(path("/me"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(me))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/teams/platform/notes"))
+        .respond_with(
+            ResponseTemplate::new(201).set_body_json(sample_single_note_response(
+                Some("platform"),
+                "Internal",
+                "# Internal",
+            )),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+    let client = ApiClient::with_options(
+        "test-token",
+        Some(&server.uri()),
+        Some(ApiClientOptions {
+            team_visibility_guard: Some(TeamVisibilityGuard::Reject),
+            ..Default::default()
+        }),
+    )
+    .unwrap();
+
+    let error = client
+        .create_team_note(
+            "platform",
+            &CreateNoteOptions {
+                title: Some("Leaked".to_string()),
+                read_permission: Some(NotePermissionRole::Guest),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(&error, ApiError::Validation(err) if err.message.contains("private team platform")),
+        "unexpected error: {error}"
+    );
+
+    client
+        .create_team_note(
+            "platform",
+            &CreateNoteOptions {
+                title: Some("Internal".to_string()),
+                read_permission: Some(NotePermissionRole::Owner),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+    assert!(client
+        .create_team_note(
+            "platform",
+            &CreateNoteOptions {
+                read_permission: Some(NotePermissionRole::SignedIn),
+                ..Default::default()
+            },
+        )
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn get_note_with_headers_returns_response_meta() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/notes/note-123"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(sample_single_note_response(None, "Cached", "# Cached"))
+                .insert_header("etag", "W/\"abc123\"")
+                .insert_header("last-modified", "Wed, 21 Oct 2015 07:28:00 GMT")
+                .insert_header("x-ratelimit-userlimit", "100")
+                .insert_header("x-ratelimit-userremaining", "42")
+                .insert_header("x-ratelimit-userreset", "1710000000"),
+        )
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/notes/note-bare"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(sample_single_note_response(None, "Bare", "# Bare")),
+        )
+        .mount(&server)
+        .await;
+    let client = ApiClient::with_base_url("test-token", &server.uri()).unwrap();
+
+    let (note, meta) = client.get_note_with_headers("note-123").await.unwrap();
+    assert_eq!(note.note.title, "Cached");
+    assert_eq!(meta.etag.as_deref(), Some("W/\"abc123\""));
+    assert_eq!(
+        meta.last_modified.as_deref(),
+        Some("Wed, 21 Oct 2015 07:28:00 GMT")
+    );
+    assert_eq!(
+        meta.rate_limit,
+        Some(RateLimitStatus {
+            limit: 100,
+            remaining: 42,
+            reset_at: Some(1_710_000_000),
+        })
+    );
+
+    let (_, meta) = client.get_note_with_headers("note-bare").await.unwrap();
+    assert_eq!(meta, ResponseMeta::default());
+}
+
+#[tokio::test]
+async fn fallback_base_url_is_used_after_primary_retries_are_exhausted() {
+    let primary = MockServer::start().await;
+    let fallback = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/v1/me"))
+        .respond_with(ResponseTemplate::new(500))
+        .expect(3)
+        .mount(&primary)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/v1/me"))
+        .and(header("authorization", "Bearer test-token"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(sample_user_response()))
+        .expect(1)
+        .mount(&fallback)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/v1/notes/missing"))
+        .respond_with(ResponseTemplate::new(404))
+        .expect(1)
+        .mount(&primary)
+        .await;
+    let options = ApiClientOptions {
+        retry_options: Some(RetryOptions {
+            max_retries: 2,
+            base_delay: time::Duration::from_millis(1),
+            ..Default::default()
+        }),
+        fallback_base_url: Some(format!("{}/v1", fallback.uri()).parse().unwrap()),
+        ..Default::default()
+    };
+    let client = ApiClient::with_options(
+        "test-token",
+        Some(&format!("{}/v1", primary.uri())),
+        Some(options),
+    )
+    .unwrap();
+
+    let (user, stats) = client.get_me_with_stats().await.unwrap();
+    assert_eq!(user.id, "user-123");
+    assert_eq!(stats.attempts, 4);
+
+    let error = client.get_note("missing").await.unwrap_err();
+    assert!(matches!(error, ApiError::HttpResponse(err) if err.code == 404));
+}
+
+#[tokio::test]
+async fn batch_updater_shutdown_flushes_pending_updates() {
+    let server = MockServer::start().await;
+    for note_id in ["note-a", "note-b"] {
+        Mock::given(method("PATCH"))
+            .and(path(format!("/notes/{note_id}")))
+            .and(body_json(json!({ "content": format!("{note_id} final") })))
+            .respond_with(ResponseTemplate::new(202))
+            .expect(1)
+            .mount(&server)
+            .await;
+    }
+
+    let client = ApiClient::with_base_url("test-token", &server.uri()).unwrap();
+    let batch = client.batch_updater(time::Duration::from_secs(60)).unwrap();
+    for note_id in ["note-a", "note-b"] {
+        batch
+            .update_note_content(note_id, &format!("{note_id} draft"))
+            .unwrap();
+        batch
+            .update_note_content(note_id, &format!("{note_id} final"))
+            .unwrap();
+    }
+
+    let failures = batch.shutdown(time::Duration::from_secs(5)).await.unwrap();
+    assert!(failures.is_empty());
+    assert_eq!(server.received_requests().await.unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn batch_updater_shutdown_times_out_on_slow_writes() {
+    let server = MockServer::start().await;
+    Mock::given(method("PATCH"))
+        .and(path("/notes/note-123"))
+        .respond_with(ResponseTemplate::new(202).set_delay(time::Duration::from_secs(2)))
+        .mount(&server)
+        .await;
+
+    let client = ApiClient::with_base_url("test-token", &server.uri()).unwrap();
+    let batch = client.batch_updater(time::Duration::from_secs(60)).unwrap();
+    batch.update_note_content("note-123", "slow").unwrap();
+
+    let error = batch
+        .shutdown(time::Duration::from_millis(50))
+        .await
+        .unwrap_err();
+    assert!(matches!(error, ApiError::Timeout(_)));
+}
+
+#[tokio::test]
+async fn unwrap_data_envelope_reads_enveloped_and_bare_responses() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/me"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(json!({ "data": sample_user_response() })),
+        )
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/notes/note-123"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(sample_single_note_response(None, "Bare", "# Bare")),
+        )
+        .mount(&server)
+        .await;
+    let client = ApiClient::with_options(
+        "test-token",
+        Some(&server.uri()),
+        Some(ApiClientOptions {
+            unwrap_data_envelope: true,
+            ..Default::default()
+        }),
+    )
+    .unwrap();
+
+    assert_eq!(client.get_me().await.unwrap().id, "user-123");
+    assert_eq!(
+        client.get_note("note-123").await.unwrap().note.title,
+        "Bare"
+    );
+}
+
+#[cfg(feature = "cookies")]
+#[tokio::test]
+async fn cookie_store_keeps_session_cookie_across_auth_redirect() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/me"))
+        .respond_with(
+            ResponseTemplate::new(302)
+                .insert_header("set-cookie", "sso_session=abc123; Path=/")
+                .insert_header("location", format!("{}/sso/me", server.uri())),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/sso/me"))
+        .and(header("cookie", "sso_session=abc123"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(sample_user_response()))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let client = ApiClient::with_options(
+        "test-token",
+        Some(&server.uri()),
+        Some(ApiClientOptions {
+            cookie_store: true,
+            ..Default::default()
+        }),
+    )
+    .unwrap();
+
+    assert_eq!(client.get_me().await.unwrap().id, "user-123");
+}
+
+#[tokio::test]
+async fn total_content_bytes_sums_note_contents() {
+    let server = MockServer::start().await;
+    let notes = [("note-a", "# A\n"), ("note-b", "héllo 🚀")];
+    let list: Vec<_> = notes
+        .iter()
+        .map(|(id, _)| {
+            let mut note = sample_single_note_response(None, id, "");
+            note["id"] = json!(id);
+            note.as_object_mut().unwrap().remove("content");
+            note
+        })
+        .collect();
+    Mock::given(method("GET"))
+        .and(path("/notes"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(list))
+        .mount(&server)
+        .await;
+    for (id, content) in notes {
+        let mut note = sample_single_note_response(None, id, content);
+        note["id"] = json!(id);
+        Mock::given(method("GET"))
+            .and(path(format!("/notes/{id}")))
+            .respond_with(ResponseTemplate::new(200).set_body_json(note))
+            .expect(1)
+            .mount(&server)
+            .await;
+    }
+    let client = ApiClient::with_base_url("test-token", &server.uri()).unwrap();
+
+    assert_eq!(client.total_content_bytes().await.unwrap(), 4 + 11);
+}
+
+#[tokio::test]
+async fn get_notes_by_publish_type_filters_note_list() {
+    let server = MockServer::start().await;
+    let list: Vec<_> = [
+        ("note-edit", "edit"),
+        ("note-slide-1", "slide"),
+        ("note-book", "book"),
+        ("note-slide-2", "slide"),
+    ]
+    .iter()
+    .map(|(id, publish_type)| {
+        let mut note = sample_single_note_response(None, id, "");
+        note["id"] = json!(id);
+        note["publishType"] = json!(publish_type);
+        note.as_object_mut().unwrap().remove("content");
+        note
+    })
+    .collect();
+    Mock::given(method("GET"))
+        .and(path("/notes"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(list))
+        .mount(&server)
+        .await;
+    let client = ApiClient::with_base_url("test-token", &server.uri()).unwrap();
+
+    for (publish_type, expected) in [
+        (NotePublishType::Edit, vec!["note-edit"]),
+        (NotePublishType::Slide, vec!["note-slide-1", "note-slide-2"]),
+        (NotePublishType::Book, vec!["note-book"]),
+        (NotePublishType::View, vec![]),
+    ] {
+        let notes = client
+            .get_notes_by_publish_type(publish_type)
+            .await
+            .unwrap();
+        let ids: Vec<_> = notes.iter().map(|note| note.id.as_str()).collect();
+        assert_eq!(ids, expected);
+    }
+}
+
+#[tokio::test]
+async fn get_published_html_omits_authorization_header() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/s/short-123"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("<h1>Published</h1>"))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/me"))
+        .and(header("authorization", "Bearer test-token"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(sample_user_response()))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let client = ApiClient::with_base_url("test-token", &server.uri()).unwrap();
+
+    let html = client
+        .get_published_html(&format!("{}/s/short-123", server.uri()))
+        .await
+        .unwrap();
+    assert_eq!(html, "<h1>Published</h1>");
+    client.get_me().await.unwrap();
+
+    let received = server.received_requests().await.unwrap();
+    assert!(!received[0].headers.contains_key("authorization"));
+    assert!(received[1].headers.contains_key("authorization"));
+}
+
+#[tokio::test]
+async fn endpoint_retry_overrides_disable_retries_for_delete_only() {
+    let server = MockServer::start().await;
+    Mock::given(method("DELETE"))
+        .and(path("/notes/note-123"))
+        .respond_with(ResponseTemplate::new(503))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/notes/note-123"))
+        .respond_with(ResponseTemplate::new(503))
+        .up_to_n_times(2)
+        .expect(2)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/notes/note-123"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(sample_single_note_response(
+                None,
+                "Retried",
+                "# Retried",
+            )),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+    let options = ApiClientOptions {
+        retry_options: Some(RetryOptions {
+            max_retries: 3,
+            base_delay: time::Duration::from_millis(1),
+            ..Default::default()
+        }),
+        endpoint_retry_overrides: HashMap::from([("delete_note", None)]),
+        ..Default::default()
+    };
+    let client = ApiClient::with_options("test-token", Some(&server.uri()), Some(options)).unwrap();
+
+    let error = client.delete_note("note-123").await.unwrap_err();
+    assert!(matches!(error, ApiError::InternalServer(_)));
+
+    let note = client.get_note("note-123").await.unwrap();
+    assert_eq!(note.note.title, "Retried");
+}
+
+#[tokio::test]
+async fn get_note_list_serves_cached_list_on_not_modified() {
+    let server = MockServer::start().await;
+    let mut note = sample_single_note_response(None, "Cached", "");
+    note.as_object_mut().unwrap().remove("content");
+    Mock::given(method("GET"))
+        .and(path("/notes"))
+        .and(header("if-none-match", "\"list-v1\""))
+        .respond_with(ResponseTemplate::new(304))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/notes"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(json!([note]))
+                .insert_header("etag", "\"list-v1\""),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+    let client = ApiClient::with_base_url("test-token", &server.uri()).unwrap();
+
+    let downloaded = client.get_note_list().await.unwrap();
+    let cached = client.get_note_list().await.unwrap();
+
+    assert_eq!(downloaded.len(), 1);
+    assert_eq!(cached, downloaded);
+    let received = server.received_requests().await.unwrap();
+    assert_eq!(received.len(), 2);
+    assert!(!received[0].headers.contains_key("if-none-match"));
+}
+
+#[tokio::test]
+async fn far_future_rate_limit_reset_returns_429_without_waiting() {
+    let server = MockServer::start().await;
+    let reset_at = time::SystemTime::now()
+        .duration_since(time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+        + 3600;
+    Mock::given(method("GET"))
+        .and(path("/me"))
+        .respond_with(
+            ResponseTemplate::new(429)
+                .insert_header("x-ratelimit-userlimit", "100")
+                .insert_header("x-ratelimit-userremaining", "0")
+                .insert_header("x-ratelimit-userreset", reset_at.to_string().as_str()),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+    let client = fast_retry_client(&server, 3);
+
+    let started = time::Instant::now();
+    let error = client.get_me().await.unwrap_err();
+
+    assert!(matches!(error, ApiError::TooManyRequests(_)));
+    assert!(started.elapsed() < time::Duration::from_secs(5));
+}
+
+#[tokio::test]
+async fn single_flight_shares_conditional_note_list_requests() {
+    let server = MockServer::start().await;
+    let mut note = sample_single_note_response(None, "Cached", "");
+    note.as_object_mut().unwrap().remove("content");
+    Mock::given(method("GET"))
+        .and(path("/notes"))
+        .and(header("if-none-match", "\"list-v1\""))
+        .respond_with(ResponseTemplate::new(304).set_delay(time::Duration::from_millis(200)))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/notes"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(json!([note]))
+                .insert_header("etag", "\"list-v1\""),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+    let options = ApiClientOptions {
+        single_flight: true,
+        ..Default::default()
+    };
+    let client = ApiClient::with_options("test-token", Some(&server.uri()), Some(options)).unwrap();
+
+    let downloaded = client.get_note_list().await.unwrap();
+    let (first, second) = tokio::join!(client.get_note_list(), client.get_note_list());
+
+    assert_eq!(first.unwrap(), downloaded);
+    assert_eq!(second.unwrap(), downloaded);
+}
+
+#[tokio::test]
+async fn update_notes_resumes_a_panicking_update_on_the_caller() {
+    let server = MockServer::start().await;
+    Mock::given(method("PATCH"))
+        .and(path("/notes/note-123"))
+        .respond_with(ResponseTemplate::new(500))
+        .mount(&server)
+        .await;
+    let options = ApiClientOptions {
+        retry_options: Some(RetryOptions {
+            retry_predicate: Some(Arc::new(|_| panic!("retry predicate exploded"))),
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    let client = ApiClient::with_options("test-token", Some(&server.uri()), Some(options)).unwrap();
+    let payload = UpdateNoteOptions {
+        content: Some("# Updated".to_string()),
+        ..Default::default()
+    };
+
+    let update = tokio::spawn(async move { client.update_notes(&["note-123"], &payload, 2).await });
+    let error = update.await.unwrap_err();
+
+    assert!(error.is_panic());
+    let panic = error.into_panic();
+    assert_eq!(
+        panic.downcast_ref::<&str>(),
+        Some(&"retry predicate exploded")
+    );
+}
+
+#[tokio::test]
+async fn create_note_content_paths_send_a_stable_idempotency_key() {
+    let server = MockServer::start().await;
+    for note_path in ["/notes", "/teams/platform-team/notes"] {
+        Mock::given(method("POST"))
+            .and(path(note_path))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(1)
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path(note_path))
+            .respond_with(
+                ResponseTemplate::new(201)
+                    .set_body_json(sample_single_note_response(None, "Created", "")),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+    }
+
+    let options = ApiClientOptions {
+        retry_options: Some(RetryOptions {
+            base_delay: time::Duration::from_millis(1),
+            retry_non_idempotent: true,
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    let client = ApiClient::with_options("test-token", Some(&server.uri()), Some(options)).unwrap();
+    client.create_note_content("# Personal").await.unwrap();
+    client
+        .create_team_note_content("platform-team", "# Team")
+        .await
+        .unwrap();
+
+    let requests = server.received_requests().await.unwrap();
+    for note_path in ["/notes", "/teams/platform-team/notes"] {
+        let keys: Vec<_> = requests
+            .iter()
+            .filter(|request| request.url.path() == note_path)
+            .map(|request| {
+                request.headers["idempotency-key"]
+                    .to_str()
+                    .unwrap()
+                    .to_string()
+            })
+            .collect();
+        assert_eq!(keys.len(), 2);
+        assert!(!keys[0].is_empty());
+        assert_eq!(keys[0], keys[1]);
+    }
+}
+
+#[cfg(not(feature = "tracing"))]
+#[tokio::test]
+async fn team_visibility_guard_warn_skips_lookup_without_tracing() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/me"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(sample_user_response()))
+        .expect(0)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/teams/platform/notes"))
+        .respond_with(
+            ResponseTemplate::new(201).set_body_json(sample_single_note_response(
+                Some("platform"),
+                "Shared",
+                "",
+            )),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+    let client = ApiClient::with_options(
+        "test-token",
+        Some(&server.uri()),
+        Some(ApiClientOptions {
+            team_visibility_guard: Some(TeamVisibilityGuard::Warn),
+            ..Default::default()
+        }),
+    )
+    .unwrap();
+
+    client
+        .create_team_note(
+            "platform",
+            &CreateNoteOptions {
+                title: Some("Shared".to_string()),
+                read_permission: Some(NotePermissionRole::Guest),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn batch_updater_restarts_debounce_window_on_each_update() {
+    let server = MockServer::start().await;
+    Mock::given(method("PATCH"))
+        .and(path("/notes/note-123"))
+        .and(body_json(json!({ "content": "draft 2" })))
+        .respond_with(ResponseTemplate::new(202))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let client = ApiClient::with_base_url("test-token", &server.uri()).unwrap();
+    let batch = client
+        .batch_updater(time::Duration::from_millis(300))
+        .unwrap();
+
+    batch.update_note_content("note-123", "draft 1").unwrap();
+    tokio::time::sleep(time::Duration::from_millis(200)).await;
+    batch.update_note_content("note-123", "draft 2").unwrap();
+    tokio::time::sleep(time::Duration::from_millis(200)).await;
+    assert!(server.received_requests().await.unwrap().is_empty());
+
+    tokio::time::sleep(time::Duration::from_millis(400)).await;
+    assert_eq!(server.received_requests().await.unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn batch_updater_reports_every_failed_write() {
+    let server = MockServer::start().await;
+    Mock::given(method("PATCH"))
+        .respond_with(ResponseTemplate::new(404))
+        .mount(&server)
+        .await;
+
+    let client = ApiClient::with_base_url("test-token", &server.uri()).unwrap();
+    let batch = client
+        .batch_updater(time::Duration::from_millis(20))
+        .unwrap();
+
+    batch.update_note_content("note-a", "a").unwrap();
+    tokio::time::sleep(time::Duration::from_millis(200)).await;
+    batch.update_note_content("note-b", "b").unwrap();
+    batch.update_note_content("note-c", "c").unwrap();
+
+    let mut failures = batch.flush().await.unwrap();
+    failures.sort_by(|(left, _), (right, _)| left.cmp(right));
+    let note_ids: Vec<&str> = failures
+        .iter()
+        .map(|(note_id, _)| note_id.as_str())
+        .collect();
+    assert_eq!(note_ids, ["note-a", "note-b", "note-c"]);
+    assert!(failures
+        .iter()
+        .all(|(_, error)| matches!(error, ApiError::HttpResponse(error) if error.code == 404)));
+    assert!(batch.flush().await.unwrap().is_empty());
+}
@@ -0,0 +1,158 @@
+use hackmd_api_client_rs::{ApiError, Note, SingleNote, Team, User};
+use proptest::prelude::*;
+use proptest::sample::Index;
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use std::io;
+
+fn team_json() -> Value {
+    json!({
+        "id": "team-123",
+        "ownerId": "user-123",
+        "name": "Platform",
+        "logo": "https://hackmd.io/logo.png",
+        "path": "platform",
+        "description": null,
+        "visibility": "private",
+        "createdAt": 1_710_000_000_000i64,
+        "upgraded": false,
+    })
+}
+
+fn user_json() -> Value {
+    json!({
+        "id": "user-123",
+        "email": null,
+        "name": "Demo User",
+        "userPath": "demo-user",
+        "photo": "https://hackmd.io/photo.png",
+        "teams": [team_json()],
+        "upgraded": false,
+    })
+}
+
+fn single_note_json() -> Value {
+    json!({
+        "id": "note-123",
+        "title": "Sample",
+        "description": "",
+        "tags": ["rust"],
+        "lastChangedAt": 1_710_000_000_000i64,
+        "createdAt": 1_710_000_000_000i64,
+        "titleUpdatedAt": 1_710_000_000_000.5,
+        "tagsUpdatedAt": null,
+        "lastChangeUser": { "name": "Editor", "userPath": "editor", "photo": "" },
+        "publishType": "edit",
+        "publishedAt": null,
+        "userPath": "demo-user",
+        "teamPath": null,
+        "permalink": null,
+        "shortId": "short-123",
+        "publishLink": "https://hackmd.io/note-123",
+        "folderPaths": [],
+        "readPermission": "owner",
+        "writePermission": "owner",
+        "content": "# Sample",
+    })
+}
+
+fn fixtures() -> Vec<Value> {
+    vec![team_json(), user_json(), single_note_json()]
+}
+
+/// Decoding must either succeed or fail with a serde error that maps to `InvalidData`.
+fn assert_decodes_cleanly<T: DeserializeOwned>(bytes: &[u8]) {
+    if let Err(error) = serde_json::from_slice::<T>(bytes) {
+        let error = ApiError::from(error);
+        assert!(matches!(error, ApiError::Serde(_)), "unexpected {error:?}");
+        assert_eq!(io::Error::from(error).kind(), io::ErrorKind::InvalidData);
+    }
+}
+
+fn assert_all_types_decode_cleanly(bytes: &[u8]) {
+    assert_decodes_cleanly::<Note>(bytes);
+    assert_decodes_cleanly::<SingleNote>(bytes);
+    assert_decodes_cleanly::<User>(bytes);
+    assert_decodes_cleanly::<Team>(bytes);
+}
+
+fn arb_json() -> impl Strategy<Value = Value> {
+    let leaf = prop_oneof![
+        Just(Value::Null),
+        any::<bool>().prop_map(Value::from),
+        any::<i64>().prop_map(Value::from),
+        any::<u64>().prop_map(Value::from),
+        any::<f64>().prop_map(Value::from),
+        prop_oneof![Just(1e300), Just(-1e300), Just(8.64e15), Just(-8.64e15)].prop_map(Value::from),
+        ".{0,12}".prop_map(Value::from),
+        prop_oneof![Just("owner"), Just("edit"), Just("private"), Just("")].prop_map(Value::from),
+    ];
+    leaf.prop_recursive(3, 24, 4, |inner| {
+        prop_oneof![
+            prop::collection::vec(inner.clone(), 0..4).prop_map(Value::Array),
+            prop::collection::btree_map("[a-zA-Z]{1,12}", inner, 0..4)
+                .prop_map(|map| Value::Object(map.into_iter().collect())),
+        ]
+    })
+}
+
+/// Replaces or removes one top-level field of a valid fixture.
+fn mutate(mut fixture: Value, field: Index, replacement: Option<Value>) -> Value {
+    let object = fixture.as_object_mut().unwrap();
+    let key = object
+        .keys()
+        .nth(field.index(object.len()))
+        .unwrap()
+        .clone();
+    match replacement {
+        Some(value) => object.insert(key, value),
+        None => object.remove(&key),
+    };
+    fixture
+}
+
+proptest! {
+    #[test]
+    fn random_bytes_never_panic(bytes in prop::collection::vec(any::<u8>(), 0..256)) {
+        assert_all_types_decode_cleanly(&bytes);
+    }
+
+    #[test]
+    fn random_json_values_never_panic(value in arb_json()) {
+        assert_all_types_decode_cleanly(&serde_json::to_vec(&value).unwrap());
+    }
+
+    #[test]
+    fn truncated_responses_never_panic(fixture in any::<Index>(), cut in any::<Index>()) {
+        let fixtures = fixtures();
+        let bytes = serde_json::to_vec(fixture.get(&fixtures)).unwrap();
+        assert_all_types_decode_cleanly(&bytes[..cut.index(bytes.len())]);
+    }
+
+    #[test]
+    fn mutated_fields_never_panic(
+        fixture in any::<Index>(),
+        field in any::<Index>(),
+        replacement in prop::option::of(arb_json()),
+    ) {
+        let fixture = fixture.get(&fixtures()).clone();
+        let value = mutate(fixture, field, replacement);
+        assert_all_types_decode_cleanly(&serde_json::to_vec(&value).unwrap());
+    }
+}
+
+#[test]
+fn out_of_range_timestamps_are_serde_errors() {
+    for timestamp in [
+        json!(1e300),
+        json!(-1e300),
+        json!(u64::MAX),
+        json!(i64::MIN),
+    ] {
+        let mut note = single_note_json();
+        note["lastChangedAt"] = timestamp;
+
+        let error = ApiError::from(serde_json::from_value::<SingleNote>(note).unwrap_err());
+        assert!(error.to_string().contains("timestamp is out of range"));
+    }
+}
@@ -0,0 +1,34 @@
+use hackmd_api_client_rs::prelude::*;
+use std::time;
+
+#[test]
+fn prelude_covers_client_and_option_types() {
+    let options = ApiClientOptions {
+        timeout: Some(time::Duration::from_secs(10)),
+        retry_options: Some(RetryOptions {
+            max_retries: 1,
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    let client = ApiClient::with_options(
+        "test-token",
+        Some("https://api.example.com/v1"),
+        Some(options),
+    )
+    .unwrap();
+    assert_eq!(client.team("platform").team_path(), "platform");
+
+    let note = CreateNoteOptions {
+        title: Some("Prelude".to_string()),
+        read_permission: Some(NotePermissionRole::SignedIn),
+        comment_permission: Some(CommentPermissionType::Owners),
+        ..Default::default()
+    };
+    assert_eq!(note.title.as_deref(), Some("Prelude"));
+
+    assert!(matches!(
+        ApiClient::new(""),
+        Err(ApiError::MissingRequiredArgument(_))
+    ));
+}